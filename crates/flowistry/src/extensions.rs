@@ -66,12 +66,54 @@ impl FromStr for PointerMode {
   }
 }
 
+/// Whether Flowistry should consider the program location when computing aliases
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize, Hash)]
+pub enum AliasMode {
+  /// Imprecise behavior, a reference aliases every loan it could hold anywhere in the body
+  FlowInsensitive,
+  /// Precise behavior, a reference only aliases the loans it could hold at a given location
+  FlowSensitive,
+}
+
+impl FromStr for AliasMode {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "FlowInsensitive" => Ok(Self::FlowInsensitive),
+      "FlowSensitive" => Ok(Self::FlowSensitive),
+      _ => Err(format!("Could not parse: {s}")),
+    }
+  }
+}
+
+/// Whether Flowistry should add control dependencies to the dependencies of a location
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize, Hash)]
+pub enum ControlMode {
+  /// Sound behavior, a location depends on the conditions that decide whether it executes
+  Include,
+  /// Unsound behavior, only track data dependencies
+  Ignore,
+}
+
+impl FromStr for ControlMode {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "Include" => Ok(Self::Include),
+      "Ignore" => Ok(Self::Ignore),
+      _ => Err(format!("Could not parse: {s}")),
+    }
+  }
+}
+
 /// A combination of all the precision levers.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Hash)]
 pub struct EvalMode {
   pub mutability_mode: MutabilityMode,
   pub context_mode: ContextMode,
   pub pointer_mode: PointerMode,
+  pub alias_mode: AliasMode,
+  pub control_mode: ControlMode,
 }
 
 impl Default for EvalMode {
@@ -80,6 +122,8 @@ impl Default for EvalMode {
       mutability_mode: MutabilityMode::DistinguishMut,
       context_mode: ContextMode::SigOnly,
       pointer_mode: PointerMode::Precise,
+      alias_mode: AliasMode::FlowInsensitive,
+      control_mode: ControlMode::Include,
     }
   }
 }
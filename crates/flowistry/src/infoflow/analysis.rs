@@ -1,4 +1,8 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+  borrow::Cow,
+  cell::{Cell, RefCell},
+  rc::Rc,
+};
 
 // use indexical::impls::RustcIndexMatrix as IndexMatrix;
 use indexical::bitset::rustc::IndexMatrix;
@@ -24,10 +28,13 @@ use smallvec::SmallVec;
 
 use super::{
   FlowResults,
+  caveats::{Caveat, CaveatKind, CaveatSet},
   mutation::{ModularMutationVisitor, Mutation, MutationStatus},
 };
 use crate::{
-  extensions::{ContextMode, MutabilityMode, is_extension_active},
+  extensions::{
+    AliasMode, ContextMode, ControlMode, MutabilityMode, is_extension_active,
+  },
   mir::placeinfo::PlaceInfo,
 };
 
@@ -74,6 +81,9 @@ pub struct FlowAnalysis<'a, 'tcx> {
 
   pub(crate) control_dependencies: ControlDependencies<BasicBlock>,
   pub(crate) recurse_cache: RefCell<HashMap<BodyId, FlowResults<'a, 'tcx>>>,
+  pub(crate) caveats: CaveatSet,
+  pub(crate) definite_only: bool,
+  pub(crate) possible_mutations: Cell<bool>,
 }
 
 impl<'a, 'tcx> FlowAnalysis<'a, 'tcx> {
@@ -94,9 +104,36 @@ impl<'a, 'tcx> FlowAnalysis<'a, 'tcx> {
       place_info,
       control_dependencies,
       recurse_cache,
+      caveats: CaveatSet::default(),
+      definite_only: false,
+      possible_mutations: Cell::new(false),
     }
   }
 
+  /// If `definite_only` is true, then mutations that only possibly happen are ignored,
+  /// including within the bodies of recursively analyzed callees.
+  pub fn with_definite_only(mut self, definite_only: bool) -> Self {
+    self.definite_only = definite_only;
+    self
+  }
+
+  /// Returns every place where the analysis took an unsound shortcut, so the
+  /// dependencies it computed may be incomplete.
+  pub fn caveats(&self) -> Vec<Caveat> {
+    let mut caveats = self.place_info.caveats().to_vec();
+    caveats.extend(self.caveats.to_vec());
+    caveats
+  }
+
+  /// Returns true if the analysis encountered a mutation that only possibly happens,
+  /// either in this body or in a recursively analyzed callee.
+  ///
+  /// If not, every dependency is definite and [`compute_definite_flow`](super::compute_definite_flow)
+  /// would return the same results.
+  pub fn has_possible_mutations(&self) -> bool {
+    self.possible_mutations.get()
+  }
+
   /// Returns the [`LocationOrArgDomain`] used by the analysis.
   pub fn location_domain(&self) -> &Rc<LocationOrArgDomain> {
     self.place_info.location_domain()
@@ -149,6 +186,20 @@ impl<'a, 'tcx> FlowAnalysis<'a, 'tcx> {
     location: Location,
   ) {
     debug!("  Applying mutations {mutations:?}");
+    if mutations
+      .iter()
+      .any(|mt| matches!(mt.status, MutationStatus::Possibly))
+    {
+      self.possible_mutations.set(true);
+    }
+    let mutations = if self.definite_only {
+      mutations
+        .into_iter()
+        .filter(|mt| matches!(mt.status, MutationStatus::Definitely))
+        .collect()
+    } else {
+      mutations
+    };
     let location_domain = self.location_domain();
 
     // Initialize dependencies to include current location of mutation.
@@ -179,7 +230,12 @@ impl<'a, 'tcx> FlowAnalysis<'a, 'tcx> {
     }
 
     // Add location of every control dependency.
-    let controlled_by = self.control_dependencies.dependent_on(location.block);
+    let ignore_control =
+      is_extension_active(|mode| mode.control_mode == ControlMode::Ignore);
+    let controlled_by = self
+      .control_dependencies
+      .dependent_on(location.block)
+      .filter(|_| !ignore_control);
     let body = self.body;
     for block in controlled_by.into_iter().flat_map(|set| set.iter()) {
       for deps in &mut all_deps {
@@ -199,6 +255,9 @@ impl<'a, 'tcx> FlowAnalysis<'a, 'tcx> {
 
     let ignore_mut =
       is_extension_active(|mode| mode.mutability_mode == MutabilityMode::IgnoreMut);
+    let flow_sensitive =
+      is_extension_active(|mode| mode.alias_mode == AliasMode::FlowSensitive);
+    let globals = self.place_info.globals();
     for (mt, deps) in mutations.iter().zip(&mut all_deps) {
       // Clear sub-places of mutated place (if sound to do so)
       if matches!(mt.status, MutationStatus::Definitely)
@@ -212,13 +271,24 @@ impl<'a, 'tcx> FlowAnalysis<'a, 'tcx> {
       // Add deps of mutated to include provenance of mutated pointers
       add_deps(state, mt.mutated, deps);
 
-      let mutable_aliases = self
-        .place_info
-        .aliases(mt.mutated)
+      let aliases = if flow_sensitive {
+        Cow::Owned(self.place_info.aliases_at(mt.mutated, location))
+      } else {
+        Cow::Borrowed(self.place_info.aliases(mt.mutated))
+      };
+      let mutable_aliases = aliases
         .iter()
         .filter(|alias| {
           // Remove any conflicts that aren't actually mutable, e.g. if x : &T ends up
           // as an alias of y: &mut T. See test function_lifetime_alias_mut for an example.
+          // Globals with interior mutability are the exception, since they are always
+          // accessed through a shared reference.
+          if globals
+            .global_of(**alias)
+            .is_some_and(|def_id| globals.is_interior_mutable(def_id))
+          {
+            return true;
+          }
           let has_immut = alias.iter_projections().any(|(sub_place, _)| {
             let ty = sub_place.ty(body.local_decls(), self.tcx).ty;
             matches!(ty.ref_mutability(), Some(Mutability::Not))
@@ -276,6 +346,15 @@ impl<'a, 'tcx> Analysis<'tcx> for FlowAnalysis<'a, 'tcx> {
     terminator: &'mir Terminator<'tcx>,
     location: Location,
   ) -> TerminatorEdges<'mir, 'tcx> {
+    if matches!(terminator.kind, TerminatorKind::InlineAsm { .. }) {
+      self.caveats.add(
+        CaveatKind::InlineAsm,
+        location,
+        self.body,
+        "the effects of inline assembly are ignored".into(),
+      );
+    }
+
     if matches!(terminator.kind, TerminatorKind::Call { .. })
       && is_extension_active(|mode| mode.context_mode == ContextMode::Recurse)
       && self.recurse_into_call(state, &terminator.kind, location)
@@ -0,0 +1,140 @@
+//! Places where the analysis takes an unsound shortcut.
+//!
+//! Flowistry trades soundness for precision and performance in a few known cases,
+//! such as ignoring the effects of inline assembly. A [`Caveat`] records where this
+//! happened in a body, so a client can warn that a slice may be missing dependencies.
+
+use std::cell::RefCell;
+
+use rustc_middle::mir::{Body, Location};
+use rustc_span::Span;
+use rustc_utils::mir::location_or_arg::LocationOrArg;
+use serde::Serialize;
+
+/// The kind of unsound shortcut taken by the analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum CaveatKind {
+  /// Pointers nested too deeply within an argument are not tracked as aliases.
+  PointerLimit,
+  /// A callee containing `unsafe` blocks was analyzed as if it were safe code.
+  UnsafeCallee,
+  /// The effects of inline assembly are ignored.
+  InlineAsm,
+  /// A callee defined in another crate was approximated from its type signature.
+  ReachedLibrary,
+}
+
+/// An unsound shortcut taken by the analysis at a particular location.
+#[derive(Debug, Clone)]
+pub struct Caveat {
+  /// The kind of shortcut.
+  pub kind: CaveatKind,
+
+  /// Where in the body the shortcut was taken.
+  pub location: LocationOrArg,
+
+  /// The source span of `location`.
+  pub span: Span,
+
+  /// A human-readable explanation of what may be missing.
+  pub description: String,
+}
+
+impl Caveat {
+  /// Creates a caveat, computing its span from `location` in `body`.
+  pub fn new(
+    kind: CaveatKind,
+    location: LocationOrArg,
+    body: &Body<'_>,
+    description: impl Into<String>,
+  ) -> Self {
+    let span = match location {
+      LocationOrArg::Arg(local) => body.local_decls[local].source_info.span,
+      LocationOrArg::Location(location) => body.source_info(location).span,
+    };
+    Caveat {
+      kind,
+      location,
+      span,
+      description: description.into(),
+    }
+  }
+}
+
+/// A collection of caveats that ignores duplicates, since the fixpoint can
+/// visit the same location many times.
+#[derive(Default)]
+pub(crate) struct CaveatSet(RefCell<Vec<Caveat>>);
+
+impl CaveatSet {
+  pub(crate) fn add(
+    &self,
+    kind: CaveatKind,
+    location: Location,
+    body: &Body<'_>,
+    description: String,
+  ) {
+    let location = LocationOrArg::Location(location);
+    let mut caveats = self.0.borrow_mut();
+    let exists = caveats.iter().any(|caveat| {
+      caveat.kind == kind
+        && caveat.location == location
+        && caveat.description == description
+    });
+    if !exists {
+      caveats.push(Caveat::new(kind, location, body, description));
+    }
+  }
+
+  pub(crate) fn to_vec(&self) -> Vec<Caveat> {
+    self.0.borrow().clone()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use fluid_let::fluid_set;
+
+  use super::*;
+  use crate::{
+    extensions::{ContextMode, EVAL_MODE, EvalMode},
+    infoflow, test_utils,
+  };
+
+  #[test]
+  fn test_caveats() {
+    let input = r#"
+fn main() {
+  let mut x = 0;
+  write(&mut x);
+  let y = std::mem::replace(&mut x, 2);
+  unsafe { std::arch::asm!("nop"); }
+}
+
+fn write(x: *mut i32) {
+  unsafe { *x = 1; }
+}
+    "#;
+    test_utils::compile_body(input, |tcx, body_id, body_with_facts| {
+      let mode = EvalMode {
+        context_mode: ContextMode::Recurse,
+        ..Default::default()
+      };
+      fluid_set!(EVAL_MODE, &mode);
+
+      let results = infoflow::compute_flow(tcx, body_id, body_with_facts);
+      let mut kinds = results
+        .analysis
+        .caveats()
+        .into_iter()
+        .map(|caveat| caveat.kind)
+        .collect::<Vec<_>>();
+      kinds.sort_by_key(|kind| *kind as usize);
+      assert_eq!(kinds, vec![
+        CaveatKind::UnsafeCallee,
+        CaveatKind::InlineAsm,
+        CaveatKind::ReachedLibrary,
+      ]);
+    });
+  }
+}
@@ -0,0 +1,201 @@
+//! An HTML report of the flow results for a body, for debugging the analysis.
+//!
+//! When the `debug` feature is enabled and the `DUMP_MIR` environment variable is set,
+//! [`compute_flow`](super::compute_flow) writes a report for the analyzed body to
+//! `<DUMP_MIR>/<function path>.html`, or to the current directory if `DUMP_MIR` is empty.
+//!
+//! The report lists every location of the body with its MIR instruction, its source snippet,
+//! and the [`FlowDomain`](super::FlowDomain) rows after the location. Dependencies are links
+//! to the locations they refer to. The report also lists the aliases of each place from
+//! [`PlaceInfo`](crate::mir::placeinfo::PlaceInfo) and the control dependencies of each block.
+
+use std::{
+  fmt::Write,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use either::Either;
+use html_escape::encode_text;
+use rustc_data_structures::fx::FxHashSet as HashSet;
+use rustc_middle::mir::{Location, Place};
+use rustc_span::Span;
+use rustc_utils::{BodyExt, mir::location_or_arg::LocationOrArg};
+
+use super::FlowResults;
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+td, th { border: 1px solid #ccc; padding: 4px 8px; vertical-align: top; text-align: left; }
+code, pre { font-family: monospace; margin: 0; }
+:target { background: #ffe680; }
+"#;
+
+/// Writes an HTML report of `results` to `dir`, returning the path of the report.
+pub fn dump_results(results: &FlowResults<'_, '_>, dir: &Path) -> Result<PathBuf> {
+  let analysis = &results.analysis;
+  let tcx = analysis.tcx;
+  let body = analysis.body;
+  let place_info = &analysis.place_info;
+  let source_map = tcx.sess.source_map();
+  let name = tcx.def_path_str(analysis.def_id);
+
+  let anchor = |location: &LocationOrArg| match location {
+    LocationOrArg::Location(location) => format!("{location:?}"),
+    LocationOrArg::Arg(local) => format!("arg{}", local.as_usize()),
+  };
+  let link = |location: &LocationOrArg| {
+    let anchor = anchor(location);
+    format!("<a href=\"#{anchor}\">{anchor}</a>")
+  };
+  let source = |span: Span| {
+    let snippet = source_map.span_to_snippet(span).unwrap_or_default();
+    format!(
+      "<span title=\"{}\">{}</span>",
+      encode_text(&source_map.span_to_diagnostic_string(span)),
+      encode_text(&snippet)
+    )
+  };
+
+  let mut html = String::new();
+  writeln!(
+    html,
+    "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{0}</title><style>{STYLE}</style></head><body><h1><code>{0}</code></h1>",
+    encode_text(&name)
+  )?;
+
+  writeln!(
+    html,
+    "<h2>Inputs</h2><table><tr><th>Input</th><th>Local</th><th>Source</th></tr>"
+  )?;
+  for local in place_info.input_locals() {
+    let location = LocationOrArg::Arg(local);
+    writeln!(
+      html,
+      "<tr id=\"{}\"><td>{}</td><td><code>{local:?}</code></td><td><pre>{}</pre></td></tr>",
+      anchor(&location),
+      anchor(&location),
+      source(body.local_decls[local].source_info.span)
+    )?;
+  }
+  writeln!(html, "</table>")?;
+
+  writeln!(
+    html,
+    "<h2>Locations</h2><table><tr><th>Location</th><th>Instruction</th><th>Source</th><th>Dependencies after the instruction</th></tr>"
+  )?;
+  for location in body.all_locations() {
+    let instruction = match body.stmt_at(location) {
+      Either::Left(statement) => format!("{statement:?}"),
+      Either::Right(terminator) => format!("{:?}", terminator.kind),
+    };
+    let mut rows = results
+      .state_at(location)
+      .rows()
+      .map(|(place, deps)| {
+        let deps = deps.iter().map(link).collect::<Vec<_>>().join(", ");
+        format!(
+          "<code>{}</code>: {deps}",
+          encode_text(&format!("{place:?}"))
+        )
+      })
+      .collect::<Vec<_>>();
+    rows.sort();
+
+    writeln!(
+      html,
+      "<tr id=\"{location:?}\"><td>{location:?}</td><td><code>{}</code></td><td><pre>{}</pre></td><td>{}</td></tr>",
+      encode_text(&instruction),
+      source(body.source_info(location).span),
+      rows.join("<br>")
+    )?;
+  }
+  writeln!(html, "</table>")?;
+
+  writeln!(
+    html,
+    "<h2>Aliases</h2><table><tr><th>Place</th><th>Aliases</th></tr>"
+  )?;
+  let places = body
+    .all_locations()
+    .flat_map(|location| results.state_at(location).rows().map(|(place, _)| *place))
+    .collect::<HashSet<Place>>();
+  let mut places = places.into_iter().collect::<Vec<_>>();
+  places.sort_by_key(|place| format!("{place:?}"));
+  for place in places {
+    let mut aliases = place_info
+      .aliases(place)
+      .iter()
+      .map(|alias| format!("{alias:?}"))
+      .collect::<Vec<_>>();
+    aliases.sort();
+    writeln!(
+      html,
+      "<tr><td><code>{}</code></td><td><code>{}</code></td></tr>",
+      encode_text(&format!("{place:?}")),
+      encode_text(&aliases.join(", "))
+    )?;
+  }
+  writeln!(html, "</table>")?;
+
+  writeln!(
+    html,
+    "<h2>Control dependencies</h2><table><tr><th>Block</th><th>Depends on the terminators of</th></tr>"
+  )?;
+  for block in body.basic_blocks.indices() {
+    let deps = analysis
+      .control_dependencies
+      .dependent_on(block)
+      .into_iter()
+      .flat_map(|set| set.iter())
+      .map(|dep| link(&LocationOrArg::Location(body.terminator_loc(dep))))
+      .collect::<Vec<_>>();
+    let start = Location {
+      block,
+      statement_index: 0,
+    };
+    writeln!(
+      html,
+      "<tr><td><a href=\"#{start:?}\">{block:?}</a></td><td>{}</td></tr>",
+      deps.join(", ")
+    )?;
+  }
+  writeln!(html, "</table></body></html>")?;
+
+  fs::create_dir_all(dir)?;
+  let file_name = name
+    .replace("::", "__")
+    .replace(['<', '>', ' ', '{', '}', '#'], "_");
+  let path = dir.join(format!("{file_name}.html"));
+  fs::write(&path, html)?;
+  Ok(path)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::{infoflow, test_utils};
+
+  #[test]
+  fn test_dump_results() {
+    let input = r#"
+fn main() {
+  let mut x = 1;
+  if x > 0 {
+    x = 2;
+  }
+}
+    "#;
+    test_utils::compile_body(input, |tcx, body_id, body_with_facts| {
+      let results = infoflow::compute_flow(tcx, body_id, body_with_facts);
+      let dir = std::env::temp_dir().join("flowistry-dump-test");
+      let path = dump_results(&results, &dir).unwrap();
+      let html = fs::read_to_string(path).unwrap();
+      assert!(html.contains("id=\"bb0[0]\""));
+      assert!(html.contains("x = 2"));
+      assert!(html.contains("Control dependencies"));
+    });
+  }
+}
@@ -129,7 +129,7 @@ pub fn compute_dependencies<'tcx>(
     );
     debug!("all_target_deps={all_target_deps:#?}");
 
-    for arg in body.args_iter() {
+    for arg in aliases.input_locals() {
       let location = LocationOrArg::Arg(arg);
       for (target_deps, outputs) in
         iter::zip(&all_target_deps, &mut *outputs.borrow_mut())
@@ -211,6 +211,57 @@ pub fn compute_dependencies<'tcx>(
   outputs.into_inner()
 }
 
+/// Splits the output of [`compute_dependencies`] by certainty.
+///
+/// `definite_results` must come from [`compute_definite_flow`](super::compute_definite_flow)
+/// on the same body as `results`, or be `results` itself if it
+/// [has no possible mutations](super::FlowAnalysis::has_possible_mutations). For each list of targets, returns a pair `(definitely, possibly)`
+/// where `definitely` are the dependencies that are established through definite mutations alone,
+/// and `possibly` are the remaining dependencies, which rely on at least one mutation that
+/// only possibly happens.
+pub fn compute_dependencies_by_certainty<'tcx>(
+  results: &FlowResults<'_, 'tcx>,
+  definite_results: &FlowResults<'_, 'tcx>,
+  all_targets: Vec<Vec<(Place<'tcx>, LocationOrArg)>>,
+  direction: Direction,
+) -> Vec<(LocationOrArgSet, LocationOrArgSet)> {
+  let all_deps = compute_dependencies(results, all_targets.clone(), direction);
+  let definite_deps = compute_dependencies(definite_results, all_targets, direction);
+  iter::zip(all_deps, definite_deps)
+    .map(|(all, definite)| {
+      let mut definitely = LocationOrArgSet::new(results.analysis.location_domain());
+      let mut possibly = LocationOrArgSet::new(results.analysis.location_domain());
+      for location in all.iter() {
+        if definite.contains(*location) {
+          definitely.insert(*location);
+        } else {
+          possibly.insert(*location);
+        }
+      }
+      (definitely, possibly)
+    })
+    .collect()
+}
+
+/// Translates each [`Location`] in a set of dependencies to a corresponding
+/// source [`Span`] for the location.
+pub fn dependency_spans(
+  deps: &LocationOrArgSet,
+  body: &Body<'_>,
+  spanner: &Spanner,
+) -> Vec<Span> {
+  let location_spans = deps
+    .iter()
+    .flat_map(|location| {
+      spanner.location_to_spans(*location, body, EnclosingHirSpans::OuterOnly)
+    })
+    .collect::<Vec<_>>();
+
+  let merged_spans = Span::merge_overlaps(location_spans);
+  trace!("Spans: {merged_spans:?}");
+  merged_spans
+}
+
 /// Wraps [`compute_dependencies`] by translating each [`Location`] to a corresponding
 /// source [`Span`] for the location.
 pub fn compute_dependency_spans<'tcx>(
@@ -225,18 +276,7 @@ pub fn compute_dependency_spans<'tcx>(
   debug!("all_deps={all_deps:?}");
 
   all_deps
-    .into_iter()
-    .map(|deps| {
-      let location_spans = deps
-        .iter()
-        .flat_map(|location| {
-          spanner.location_to_spans(*location, body, EnclosingHirSpans::OuterOnly)
-        })
-        .collect::<Vec<_>>();
-
-      let merged_spans = Span::merge_overlaps(location_spans);
-      trace!("Spans: {merged_spans:?}");
-      merged_spans
-    })
+    .iter()
+    .map(|deps| dependency_spans(deps, body, spanner))
     .collect::<Vec<_>>()
 }
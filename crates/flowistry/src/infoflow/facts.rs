@@ -0,0 +1,281 @@
+//! Flow results exported as relational facts.
+//!
+//! [`Facts`] flattens the [`FlowResults`] of one or more bodies, along with the relevant parts
+//! of [`PlaceInfo`](crate::mir::placeinfo::PlaceInfo) and the MIR, into a set of relations whose
+//! columns are all strings. This makes the results easy to query without the rustc API, e.g. with
+//! a Datalog engine. The relations and their columns are listed in [`RELATIONS`].
+//!
+//! Locations are named like `bb0[1]`, arguments like `_1` and places like `(*_1).0`,
+//! matching the names in a [`DependenceGraph`]. Every relation other than `function`
+//! starts with the path of the function that the fact comes from, so names from
+//! different bodies do not collide.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use either::Either;
+use rustc_middle::{
+  mir::{Place, TerminatorKind, VarDebugInfoContents},
+  ty::{Ty, TyCtxt, TyKind},
+};
+use rustc_utils::{BodyExt, mir::location_or_arg::LocationOrArg};
+use serde::Serialize;
+
+use super::{
+  FlowResults,
+  graph::{DependenceGraph, LocationFlows},
+};
+
+/// Every relation exported by [`Facts`], along with the names of its columns.
+pub const RELATIONS: &[(&str, &[&str])] = &[
+  // A function that was analyzed.
+  ("function", &["function"]),
+  // An input to the function, i.e. an argument or a global, with its source name and type.
+  ("arg", &["function", "arg", "name", "type"]),
+  // A location with its source snippet and source range.
+  ("location", &["function", "location", "snippet", "source"]),
+  // A location that calls a function, with the path of the callee.
+  ("call", &["function", "location", "callee"]),
+  // A place that is read at a location.
+  ("reads", &["function", "location", "place"]),
+  // A place that is written at a location.
+  ("writes", &["function", "location", "place"]),
+  // The type of a place.
+  ("place_type", &["function", "place", "type"]),
+  // A place that may refer to the same memory as another place.
+  ("alias", &["function", "place", "alias"]),
+  // An argument or location that the value written at a location (transitively) depends on.
+  ("flows", &["function", "src", "dst"]),
+  // A direct dependency in the function's dependence graph, of kind `data` or `control`.
+  ("edge", &["function", "src", "dst", "kind"]),
+  // A location where the analysis may be unsound, see `infoflow::caveats`.
+  ("caveat", &["function", "location", "kind"]),
+];
+
+/// A set of relations of strings, see the [module-level documentation](self).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Facts {
+  relations: BTreeMap<String, BTreeSet<Vec<String>>>,
+}
+
+impl Facts {
+  /// Adds the facts about the body analyzed by `results`.
+  pub fn add_body<'tcx>(&mut self, results: &FlowResults<'_, 'tcx>) {
+    let analysis = &results.analysis;
+    let tcx = analysis.tcx;
+    let body = analysis.body;
+    let place_info = &analysis.place_info;
+    let source_map = tcx.sess.source_map();
+    let function = tcx.def_path_str(analysis.def_id);
+
+    let place_name = |place: Place<'tcx>| format!("{:?}", place_info.normalize(place));
+    let name = |location: &LocationOrArg| match location {
+      LocationOrArg::Location(location) => format!("{location:?}"),
+      LocationOrArg::Arg(local) => format!("{local:?}"),
+    };
+
+    self.add("function", vec![function.clone()]);
+
+    let globals = place_info
+      .globals()
+      .representatives()
+      .map(|(def_id, local)| (local, tcx.def_path_str(def_id)))
+      .collect::<BTreeMap<_, _>>();
+    for local in place_info.input_locals() {
+      let arg_name = globals.get(&local).cloned().or_else(|| {
+        body
+          .var_debug_info
+          .iter()
+          .find_map(|info| match &info.value {
+            VarDebugInfoContents::Place(place) if place.local == local => {
+              Some(info.name.to_string())
+            }
+            _ => None,
+          })
+      });
+      self.add("arg", vec![
+        function.clone(),
+        format!("{local:?}"),
+        arg_name.unwrap_or_default(),
+        type_name(tcx, body.local_decls[local].ty),
+      ]);
+    }
+
+    let mut places = BTreeMap::new();
+    for location in body.all_locations() {
+      let location_name = name(&LocationOrArg::Location(location));
+      let span = body.source_info(location).span;
+      self.add("location", vec![
+        function.clone(),
+        location_name.clone(),
+        source_map.span_to_snippet(span).unwrap_or_default(),
+        source_map.span_to_diagnostic_string(span),
+      ]);
+
+      if let Either::Right(terminator) = body.stmt_at(location)
+        && let TerminatorKind::Call { func, .. } = &terminator.kind
+        && let Some(constant) = func.constant()
+        && let TyKind::FnDef(def_id, _) = constant.const_.ty().kind()
+      {
+        self.add("call", vec![
+          function.clone(),
+          location_name.clone(),
+          tcx.def_path_str(*def_id),
+        ]);
+      }
+
+      let flows = LocationFlows::compute(results, location);
+      for (relation, accessed) in [("reads", &flows.reads), ("writes", &flows.writes)] {
+        for place in accessed {
+          for place in [Place::from(place.local), place_info.normalize(*place)] {
+            places.insert(place_name(place), place);
+          }
+          self.add(relation, vec![
+            function.clone(),
+            location_name.clone(),
+            place_name(*place),
+          ]);
+        }
+      }
+      for dep in flows.deps.iter() {
+        if dep != &LocationOrArg::Location(location) {
+          self.add("flows", vec![
+            function.clone(),
+            name(dep),
+            location_name.clone(),
+          ]);
+        }
+      }
+    }
+
+    for (place_str, place) in places {
+      let ty = place.ty(&body.local_decls, tcx).ty;
+      self.add("place_type", vec![
+        function.clone(),
+        place_str.clone(),
+        type_name(tcx, ty),
+      ]);
+      for alias in place_info.aliases(place).iter() {
+        let alias = place_name(*alias);
+        if alias != place_str {
+          self.add("alias", vec![function.clone(), place_str.clone(), alias]);
+        }
+      }
+    }
+
+    let graph = DependenceGraph::build(results, false);
+    for edge in &graph.edges {
+      self.add("edge", vec![
+        function.clone(),
+        graph.nodes[edge.src].name.clone(),
+        graph.nodes[edge.dst].name.clone(),
+        format!("{:?}", edge.kind).to_lowercase(),
+      ]);
+    }
+
+    for caveat in analysis.caveats() {
+      self.add("caveat", vec![
+        function.clone(),
+        name(&caveat.location),
+        format!("{:?}", caveat.kind),
+      ]);
+    }
+  }
+
+  /// Adds a tuple to a relation, returning true if the tuple is new.
+  pub fn add(&mut self, relation: &str, tuple: Vec<String>) -> bool {
+    match self.relations.get_mut(relation) {
+      Some(tuples) => tuples.insert(tuple),
+      None => {
+        self
+          .relations
+          .insert(relation.to_string(), BTreeSet::from([tuple]));
+        true
+      }
+    }
+  }
+
+  /// Returns true if the relation contains the tuple.
+  pub fn contains(&self, relation: &str, tuple: &[String]) -> bool {
+    self
+      .relations
+      .get(relation)
+      .is_some_and(|tuples| tuples.contains(tuple))
+  }
+
+  /// Returns the tuples of a relation, or nothing if the relation has no tuples.
+  pub fn get(&self, relation: &str) -> impl Iterator<Item = &Vec<String>> + '_ {
+    self.relations.get(relation).into_iter().flatten()
+  }
+
+  /// Returns all non-empty relations.
+  pub fn relations(&self) -> impl Iterator<Item = (&str, &BTreeSet<Vec<String>>)> + '_ {
+    self
+      .relations
+      .iter()
+      .map(|(relation, tuples)| (relation.as_str(), tuples))
+  }
+}
+
+fn type_name<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> String {
+  tcx.erase_regions(ty).to_string()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::{infoflow, test_utils};
+
+  #[test]
+  fn test_facts() {
+    let input = r#"
+struct Password(String);
+
+fn main() {
+  let password = Password(String::new());
+  let n = password.0.len();
+  log(n);
+}
+
+fn log(_n: usize) {}
+    "#;
+    test_utils::compile_body(input, |tcx, body_id, body_with_facts| {
+      let results = infoflow::compute_flow(tcx, body_id, body_with_facts);
+      let mut facts = Facts::default();
+      facts.add_body(&results);
+
+      for (relation, tuples) in facts.relations() {
+        let (_, columns) = RELATIONS
+          .iter()
+          .find(|(name, _)| *name == relation)
+          .unwrap();
+        assert!(tuples.iter().all(|tuple| tuple.len() == columns.len()));
+      }
+
+      assert_eq!(facts.get("function").collect::<Vec<_>>(), vec![&vec![
+        "main".to_string()
+      ]]);
+
+      // The write to `password` flows into the call to `log`
+      let location = |snippet: &str| {
+        facts
+          .get("location")
+          .find(|tuple| tuple[2] == snippet)
+          .unwrap_or_else(|| panic!("no location for {snippet}"))[1]
+          .clone()
+      };
+      let password = location("Password(String::new())");
+      let (log_call, _) = facts
+        .get("call")
+        .map(|tuple| (tuple[1].clone(), tuple[2].clone()))
+        .find(|(_, callee)| callee == "log")
+        .unwrap();
+      assert!(
+        facts
+          .get("flows")
+          .any(|tuple| tuple[1] == password && tuple[2] == log_call)
+      );
+      assert!(facts.get("place_type").any(|tuple| tuple[2] == "Password"));
+      assert!(facts.get("edge").any(|tuple| tuple[3] == "data"));
+    });
+  }
+}
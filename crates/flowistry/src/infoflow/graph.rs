@@ -0,0 +1,920 @@
+//! A program dependence graph built from the information flow results.
+//!
+//! [`FlowResults`] contain the *transitive* dependencies of each place at each location.
+//! [`DependenceGraph::build`] turns these into a graph whose nodes are locations (and arguments)
+//! and whose edges are the *direct* dependencies between them, labeled as either data or control
+//! dependencies. Optionally, the graph also contains a node for each place, with edges from the
+//! places read by a location and to the places written by it.
+//!
+//! The graph is independent of the compiler session, so it can be built once per body and then
+//! queried many times. It supports [reachability](DependenceGraph::reachable),
+//! [shortest paths](DependenceGraph::shortest_path),
+//! [dominators](DependenceGraph::dominators) and [subgraph extraction](DependenceGraph::subgraph),
+//! and can be serialized to Graphviz DOT with [`DependenceGraph::to_dot`] and to JSON
+//! with [`DependenceGraph::to_json`].
+
+use std::{cmp::Reverse, collections::VecDeque, fmt::Write};
+
+use either::Either;
+use rustc_data_structures::fx::{FxHashMap as HashMap, FxHashSet as HashSet};
+use rustc_middle::mir::{Location, Place, TerminatorKind, visit::Visitor};
+use rustc_span::Span;
+use rustc_utils::{
+  BodyExt, OperandExt,
+  mir::location_or_arg::{LocationOrArg, index::LocationOrArgSet},
+};
+use serde::Serialize;
+
+use super::{Direction, FlowResults, mutation::ModularMutationVisitor};
+
+/// The kind of program element represented by a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum NodeKind {
+  /// A MIR location, i.e. a statement or terminator.
+  Location,
+  /// The value of an argument (or global) at function entry.
+  Arg,
+  /// A MIR place.
+  Place,
+}
+
+/// The kind of dependency represented by an edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum EdgeKind {
+  /// The destination reads a value written by the source.
+  Data,
+  /// Whether the destination executes depends on the branch at the source.
+  Control,
+}
+
+/// A node in a [`DependenceGraph`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DependenceNode {
+  /// The index of the node in [`DependenceGraph::nodes`].
+  pub id: usize,
+  /// What the node represents.
+  pub kind: NodeKind,
+  /// A short identifier, e.g. `bb0[1]` for a location or `_1` for a place.
+  pub name: String,
+  /// The MIR instruction at a location, or the type of a place.
+  pub label: String,
+  /// The source code corresponding to the node, if any.
+  pub snippet: Option<String>,
+  /// The source file and range of the node, if any.
+  pub source: Option<String>,
+}
+
+/// A directed edge in a [`DependenceGraph`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct DependenceEdge {
+  /// The node that is depended on.
+  pub src: usize,
+  /// The node that depends on `src`.
+  pub dst: usize,
+  /// The kind of dependency.
+  pub kind: EdgeKind,
+  /// For a data dependency, the place through which the value flows, if known.
+  pub place: Option<String>,
+}
+
+/// A program dependence graph for a single body, see the [module-level documentation](self).
+#[derive(Debug, Clone, Serialize)]
+pub struct DependenceGraph {
+  /// The name of the function that the graph was built from.
+  pub function: String,
+  /// All nodes of the graph.
+  pub nodes: Vec<DependenceNode>,
+  /// All edges of the graph.
+  ///
+  /// The graph indexes the edges by node when it is built, so changing them directly
+  /// is not reflected in [`DependenceGraph::neighbors`]. Use [`DependenceGraph::filter_edges`] instead.
+  pub edges: Vec<DependenceEdge>,
+  #[serde(skip)]
+  locations: HashMap<LocationOrArg, usize>,
+  /// The indices in `edges` of the edges into and out of each node.
+  #[serde(skip)]
+  incoming: Vec<Vec<usize>>,
+  #[serde(skip)]
+  outgoing: Vec<Vec<usize>>,
+}
+
+impl DependenceGraph {
+  /// Builds a dependence graph from the results of [`compute_flow`](super::compute_flow).
+  ///
+  /// If `with_places` is true, then the graph also contains place nodes.
+  pub fn build(results: &FlowResults<'_, '_>, with_places: bool) -> Self {
+    let analysis = &results.analysis;
+    let tcx = analysis.tcx;
+    let body = analysis.body;
+    let place_info = &analysis.place_info;
+    let source_map = tcx.sess.source_map();
+
+    let mut builder = GraphBuilder {
+      graph: DependenceGraph {
+        function: tcx.def_path_str(analysis.def_id),
+        nodes: Vec::new(),
+        edges: Vec::new(),
+        locations: HashMap::default(),
+        incoming: Vec::new(),
+        outgoing: Vec::new(),
+      },
+      places: HashMap::default(),
+      edges: HashSet::default(),
+    };
+
+    let source = |span: Span| {
+      let snippet = source_map.span_to_snippet(span).ok();
+      let source = (!span.is_dummy()).then(|| source_map.span_to_diagnostic_string(span));
+      (snippet, source)
+    };
+
+    for local in place_info.input_locals() {
+      let (snippet, source) = source(body.local_decls[local].source_info.span);
+      builder.add_location(
+        LocationOrArg::Arg(local),
+        NodeKind::Arg,
+        format!("{local:?}"),
+        format!("{:?}", body.local_decls[local].ty),
+        snippet,
+        source,
+      );
+    }
+
+    // For each location, collect the places it reads and writes, and all of its
+    // transitive dependencies (including itself).
+    let mut reads: HashMap<Location, Vec<Place>> = HashMap::default();
+    let mut writes: HashMap<Location, Vec<Place>> = HashMap::default();
+    let mut transitive: HashMap<Location, LocationOrArgSet> = HashMap::default();
+    for location in body.all_locations() {
+      let flows = LocationFlows::compute(results, location);
+      reads.insert(location, flows.reads);
+      writes.insert(location, flows.writes);
+      transitive.insert(location, flows.deps);
+
+      let instruction = match body.stmt_at(location) {
+        Either::Left(statement) => format!("{statement:?}"),
+        Either::Right(terminator) => format!("{:?}", terminator.kind),
+      };
+      let (snippet, source) = source(body.source_info(location).span);
+      builder.add_location(
+        LocationOrArg::Location(location),
+        NodeKind::Location,
+        format!("{location:?}"),
+        instruction,
+        snippet,
+        source,
+      );
+    }
+
+    // The strict ancestors of a location are its transitive dependencies, except for
+    // the locations on a cycle with it (including itself).
+    let strict = transitive
+      .iter()
+      .map(|(location, deps)| {
+        let mut strict = deps.clone();
+        for dep in deps.iter() {
+          if let LocationOrArg::Location(dep) = dep
+            && transitive[dep].contains(LocationOrArg::Location(*location))
+          {
+            strict.remove(LocationOrArg::Location(*dep));
+          }
+        }
+        (*location, strict)
+      })
+      .collect::<HashMap<_, _>>();
+
+    for location in body.all_locations() {
+      let control_deps = analysis
+        .control_dependencies
+        .dependent_on(location.block)
+        .into_iter()
+        .flat_map(|blocks| blocks.iter())
+        .map(|block| LocationOrArg::Location(body.terminator_loc(block)))
+        .collect::<HashSet<_>>();
+      for dep in &control_deps {
+        builder.add_edge(
+          *dep,
+          LocationOrArg::Location(location),
+          EdgeKind::Control,
+          None,
+        );
+      }
+
+      // A dependency is direct unless it flows through another dependency,
+      // i.e. it is a strict ancestor of another dependency. A strict ancestor has fewer
+      // transitive dependencies, so visiting the dependencies from the most to the fewest,
+      // each one is either covered by a direct dependency visited before it or is direct.
+      let mut deps = transitive[&location]
+        .iter()
+        .filter(|dep| **dep != LocationOrArg::Location(location))
+        .copied()
+        .collect::<Vec<_>>();
+      deps.sort_by_key(|dep| {
+        Reverse(match dep {
+          LocationOrArg::Location(dep) => transitive[dep].len(),
+          LocationOrArg::Arg(_) => 0,
+        })
+      });
+      let mut covered = LocationOrArgSet::new(analysis.location_domain());
+      let mut direct = Vec::new();
+      for dep in deps {
+        if covered.contains(dep) {
+          continue;
+        }
+        if let LocationOrArg::Location(dep) = dep {
+          covered.union(&strict[&dep]);
+        }
+        direct.push(dep);
+      }
+
+      for dep in &direct {
+        if control_deps.contains(dep) {
+          continue;
+        }
+        let place = match dep {
+          LocationOrArg::Location(dep) => {
+            let written = writes.get(dep).map(Vec::as_slice).unwrap_or_default();
+            let read = reads.get(&location).map(Vec::as_slice).unwrap_or_default();
+            written
+              .iter()
+              .find(|written| {
+                read.iter().any(|read| {
+                  place_info
+                    .aliases(*read)
+                    .iter()
+                    .any(|alias| place_info.conflicts(*alias).contains(*written))
+                })
+              })
+              .or(written.first())
+              .map(|place| format!("{:?}", place_info.normalize(*place)))
+          }
+          LocationOrArg::Arg(local) => Some(format!("{local:?}")),
+        };
+        builder.add_edge(
+          *dep,
+          LocationOrArg::Location(location),
+          EdgeKind::Data,
+          place,
+        );
+      }
+
+      if with_places {
+        let node = builder.graph.locations[&LocationOrArg::Location(location)];
+        for place in reads.get(&location).into_iter().flatten() {
+          let (place_node, name) = builder.add_place(*place, results);
+          builder.add_edge_ids(place_node, node, EdgeKind::Data, Some(name));
+        }
+        for place in writes.get(&location).into_iter().flatten() {
+          let (place_node, name) = builder.add_place(*place, results);
+          builder.add_edge_ids(node, place_node, EdgeKind::Data, Some(name));
+        }
+      }
+    }
+
+    builder.graph.index_edges()
+  }
+
+  fn index_edges(mut self) -> Self {
+    self.incoming = vec![Vec::new(); self.nodes.len()];
+    self.outgoing = vec![Vec::new(); self.nodes.len()];
+    for (i, edge) in self.edges.iter().enumerate() {
+      self.outgoing[edge.src].push(i);
+      self.incoming[edge.dst].push(i);
+    }
+    self
+  }
+
+  /// Returns the node for a location or argument, if it is in the graph.
+  pub fn node(&self, location: LocationOrArg) -> Option<usize> {
+    self.locations.get(&location).copied()
+  }
+
+  /// Returns the edges into `node` if `direction` is [`Direction::Backward`],
+  /// the edges out of `node` if it is [`Direction::Forward`], or both.
+  pub fn neighbors(
+    &self,
+    node: usize,
+    direction: Direction,
+  ) -> impl Iterator<Item = &DependenceEdge> + '_ {
+    let outgoing = match direction {
+      Direction::Forward | Direction::Both => self.outgoing[node].as_slice(),
+      Direction::Backward => &[],
+    };
+    let incoming = match direction {
+      Direction::Backward | Direction::Both => self.incoming[node].as_slice(),
+      Direction::Forward => &[],
+    };
+    // With both directions, a self-loop is only returned once.
+    let incoming = incoming.iter().filter(move |i| {
+      !matches!(direction, Direction::Both) || self.edges[**i].src != node
+    });
+    outgoing.iter().chain(incoming).map(|i| &self.edges[*i])
+  }
+
+  /// Returns all nodes reachable from `sources` in the given direction, including the sources.
+  ///
+  /// [`Direction::Backward`] gives everything the sources depend on, and
+  /// [`Direction::Forward`] gives everything that depends on the sources.
+  /// [`Direction::Both`] is the union of the two, not the connected component.
+  pub fn reachable(
+    &self,
+    sources: impl IntoIterator<Item = usize>,
+    direction: Direction,
+  ) -> HashSet<usize> {
+    let sources = sources.into_iter().collect::<Vec<_>>();
+    match direction {
+      Direction::Both => {
+        let mut forward = self.reachable(sources.iter().copied(), Direction::Forward);
+        forward.extend(self.reachable(sources, Direction::Backward));
+        forward
+      }
+      _ => {
+        let adjacency = self.adjacency(direction);
+        let mut visited = HashSet::default();
+        let mut stack = sources;
+        while let Some(node) = stack.pop() {
+          if visited.insert(node) {
+            stack.extend(adjacency[node].iter().map(|(next, _)| *next));
+          }
+        }
+        visited
+      }
+    }
+  }
+
+  /// Returns a shortest chain of dependencies from `src` to `dst`, i.e. the nodes along a path
+  /// that follows the direction of the edges, including both endpoints.
+  pub fn shortest_path(&self, src: usize, dst: usize) -> Option<Vec<usize>> {
+    let adjacency = self.adjacency(Direction::Forward);
+    let mut parents = HashMap::default();
+    parents.insert(src, src);
+    let mut queue = VecDeque::from([src]);
+    while let Some(node) = queue.pop_front() {
+      if node == dst {
+        let mut path = vec![dst];
+        let mut node = dst;
+        while node != src {
+          node = parents[&node];
+          path.push(node);
+        }
+        path.reverse();
+        return Some(path);
+      }
+      for (next, _) in &adjacency[node] {
+        if !parents.contains_key(next) {
+          parents.insert(*next, node);
+          queue.push_back(*next);
+        }
+      }
+    }
+    None
+  }
+
+  /// Returns the nodes that every chain of dependencies into `target` passes through,
+  /// ordered from the closest to `target` to the farthest.
+  ///
+  /// The chains start at the nodes that `target` depends on which have no dependencies
+  /// of their own, such as arguments and constants. For example, if `y = f(x)` and `z = y + 1`
+  /// then the write to `y` dominates `z`, but if `z = y + x` then it does not, since `x`
+  /// reaches `z` directly.
+  pub fn dominators(&self, target: usize) -> Vec<usize> {
+    // Restrict the graph to the ancestors of target, numbered densely with a virtual root at the end.
+    let mut ancestors = self
+      .reachable([target], Direction::Backward)
+      .into_iter()
+      .collect::<Vec<_>>();
+    ancestors.sort_unstable();
+    let index = ancestors
+      .iter()
+      .enumerate()
+      .map(|(i, node)| (*node, i))
+      .collect::<HashMap<_, _>>();
+    let root = ancestors.len();
+    let mut successors = vec![Vec::new(); root + 1];
+    let mut predecessors = vec![Vec::new(); root + 1];
+    for edge in &self.edges {
+      if let (Some(src), Some(dst)) = (index.get(&edge.src), index.get(&edge.dst)) {
+        successors[*src].push(*dst);
+        predecessors[*dst].push(*src);
+      }
+    }
+
+    // Connect the root to every node without predecessors, and then to any node of a cycle
+    // that is still unreachable, computing a depth-first postorder from the root.
+    let mut order = Vec::new();
+    let mut visited = vec![false; root + 1];
+    let (sources, rest): (Vec<_>, Vec<_>) =
+      (0 .. root).partition(|node| predecessors[*node].is_empty());
+    for start in sources.into_iter().chain(rest) {
+      if visited[start] {
+        continue;
+      }
+      successors[root].push(start);
+      predecessors[start].push(root);
+      let mut stack = vec![(start, 0)];
+      visited[start] = true;
+      while let Some((node, i)) = stack.pop() {
+        match successors[node].get(i) {
+          Some(next) => {
+            stack.push((node, i + 1));
+            if !visited[*next] {
+              visited[*next] = true;
+              stack.push((*next, 0));
+            }
+          }
+          None => order.push(node),
+        }
+      }
+    }
+    order.push(root);
+    order.reverse();
+
+    // Cooper, Harvey and Kennedy's "A Simple, Fast Dominance Algorithm".
+    let mut rpo = vec![0; root + 1];
+    for (i, node) in order.iter().enumerate() {
+      rpo[*node] = i;
+    }
+    let mut idom = vec![None; root + 1];
+    idom[root] = Some(root);
+    let intersect = |idom: &[Option<usize>], mut a: usize, mut b: usize| {
+      while a != b {
+        while rpo[a] > rpo[b] {
+          a = idom[a].unwrap();
+        }
+        while rpo[b] > rpo[a] {
+          b = idom[b].unwrap();
+        }
+      }
+      a
+    };
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for node in order.iter().skip(1) {
+        let new_idom = predecessors[*node]
+          .iter()
+          .filter(|pred| idom[**pred].is_some())
+          .copied()
+          .reduce(|a, b| intersect(&idom, a, b));
+        if new_idom.is_some() && idom[*node] != new_idom {
+          idom[*node] = new_idom;
+          changed = true;
+        }
+      }
+    }
+
+    let mut dominators = Vec::new();
+    let mut node = index[&target];
+    while let Some(parent) = idom[node]
+      && parent != root
+    {
+      dominators.push(ancestors[parent]);
+      node = parent;
+    }
+    dominators
+  }
+
+  /// Returns the subgraph induced by `nodes`, with nodes renumbered in their original order.
+  ///
+  /// For example, the backward slice of a node is
+  /// `graph.subgraph(&graph.reachable([node], Direction::Backward))`.
+  pub fn subgraph(&self, nodes: &HashSet<usize>) -> DependenceGraph {
+    let mut renumber = HashMap::default();
+    let nodes = self
+      .nodes
+      .iter()
+      .filter(|node| nodes.contains(&node.id))
+      .enumerate()
+      .map(|(id, node)| {
+        renumber.insert(node.id, id);
+        DependenceNode { id, ..node.clone() }
+      })
+      .collect();
+    let edges = self
+      .edges
+      .iter()
+      .filter_map(|edge| {
+        Some(DependenceEdge {
+          src: *renumber.get(&edge.src)?,
+          dst: *renumber.get(&edge.dst)?,
+          ..edge.clone()
+        })
+      })
+      .collect();
+    let locations = self
+      .locations
+      .iter()
+      .filter_map(|(location, id)| Some((*location, *renumber.get(id)?)))
+      .collect();
+    DependenceGraph {
+      function: self.function.clone(),
+      nodes,
+      edges,
+      locations,
+      incoming: Vec::new(),
+      outgoing: Vec::new(),
+    }
+    .index_edges()
+  }
+
+  /// Returns a copy of the graph with only the edges that satisfy `keep`,
+  /// e.g. only data dependencies.
+  pub fn filter_edges(&self, keep: impl Fn(&DependenceEdge) -> bool) -> DependenceGraph {
+    DependenceGraph {
+      edges: self
+        .edges
+        .iter()
+        .filter(|edge| keep(edge))
+        .cloned()
+        .collect(),
+      ..self.clone()
+    }
+    .index_edges()
+  }
+
+  fn adjacency(&self, direction: Direction) -> Vec<Vec<(usize, &DependenceEdge)>> {
+    let mut adjacency = vec![Vec::new(); self.nodes.len()];
+    for edge in &self.edges {
+      match direction {
+        Direction::Forward => adjacency[edge.src].push((edge.dst, edge)),
+        Direction::Backward => adjacency[edge.dst].push((edge.src, edge)),
+        Direction::Both => {
+          adjacency[edge.src].push((edge.dst, edge));
+          adjacency[edge.dst].push((edge.src, edge));
+        }
+      }
+    }
+    adjacency
+  }
+
+  /// Serializes the graph in the Graphviz DOT format.
+  ///
+  /// Control dependencies are drawn as dashed edges.
+  pub fn to_dot(&self) -> String {
+    let escape = |s: &str| {
+      s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+    };
+    let mut dot = String::new();
+    writeln!(dot, "digraph \"{}\" {{", escape(&self.function)).unwrap();
+    writeln!(dot, "  node [shape=box, fontname=monospace];").unwrap();
+    for node in &self.nodes {
+      let mut label = format!("{}: {}", node.name, node.label);
+      if let Some(snippet) = &node.snippet {
+        let mut lines = snippet.lines();
+        label.push('\n');
+        label.push_str(lines.next().unwrap_or_default());
+        if lines.next().is_some() {
+          label.push_str(" ...");
+        }
+      }
+      let shape = match node.kind {
+        NodeKind::Location => "box",
+        NodeKind::Arg => "invhouse",
+        NodeKind::Place => "ellipse",
+      };
+      writeln!(
+        dot,
+        "  n{} [label=\"{}\", shape={shape}];",
+        node.id,
+        escape(&label)
+      )
+      .unwrap();
+    }
+    for edge in &self.edges {
+      let style = match edge.kind {
+        EdgeKind::Data => "solid",
+        EdgeKind::Control => "dashed",
+      };
+      let label = match &edge.place {
+        Some(place) => format!(", label=\"{}\"", escape(place)),
+        None => String::new(),
+      };
+      writeln!(
+        dot,
+        "  n{} -> n{} [style={style}{label}];",
+        edge.src, edge.dst
+      )
+      .unwrap();
+    }
+    writeln!(dot, "}}").unwrap();
+    dot
+  }
+
+  /// Serializes the graph as JSON.
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap()
+  }
+}
+
+/// The places read and written at a location, and the transitive dependencies of the location.
+pub(crate) struct LocationFlows<'tcx> {
+  pub(crate) reads: Vec<Place<'tcx>>,
+  pub(crate) writes: Vec<Place<'tcx>>,
+  /// Includes the location itself.
+  pub(crate) deps: LocationOrArgSet,
+}
+
+impl<'tcx> LocationFlows<'tcx> {
+  pub(crate) fn compute(results: &FlowResults<'_, 'tcx>, location: Location) -> Self {
+    let analysis = &results.analysis;
+    let body = analysis.body;
+    let state = results.state_at(location);
+    let mut flows = LocationFlows {
+      reads: Vec::new(),
+      writes: Vec::new(),
+      deps: LocationOrArgSet::new(analysis.location_domain()),
+    };
+    if let Either::Right(terminator) = body.stmt_at(location)
+      && let TerminatorKind::SwitchInt { discr, .. } = &terminator.kind
+      && let Some(place) = discr.as_place()
+    {
+      flows.deps.union(&analysis.deps_for(state, place));
+      flows.reads.push(place);
+    }
+    ModularMutationVisitor::new(&analysis.place_info, |_, mutations| {
+      for mutation in mutations {
+        flows
+          .deps
+          .union(&analysis.deps_for(state, mutation.mutated));
+        flows.reads.extend(mutation.inputs);
+        flows.writes.push(mutation.mutated);
+      }
+    })
+    .visit_location(body, location);
+    flows.deps.insert(location);
+    flows
+  }
+}
+
+struct GraphBuilder<'tcx> {
+  graph: DependenceGraph,
+  places: HashMap<Place<'tcx>, usize>,
+  edges: HashSet<(usize, usize, EdgeKind)>,
+}
+
+impl<'tcx> GraphBuilder<'tcx> {
+  fn add_node(
+    &mut self,
+    kind: NodeKind,
+    name: String,
+    label: String,
+    snippet: Option<String>,
+    source: Option<String>,
+  ) -> usize {
+    let id = self.graph.nodes.len();
+    self.graph.nodes.push(DependenceNode {
+      id,
+      kind,
+      name,
+      label,
+      snippet,
+      source,
+    });
+    id
+  }
+
+  fn add_location(
+    &mut self,
+    location: LocationOrArg,
+    kind: NodeKind,
+    name: String,
+    label: String,
+    snippet: Option<String>,
+    source: Option<String>,
+  ) {
+    let id = self.add_node(kind, name, label, snippet, source);
+    self.graph.locations.insert(location, id);
+  }
+
+  fn add_place(
+    &mut self,
+    place: Place<'tcx>,
+    results: &FlowResults<'_, 'tcx>,
+  ) -> (usize, String) {
+    let place = results.analysis.place_info.normalize(place);
+    let name = format!("{place:?}");
+    if let Some(id) = self.places.get(&place) {
+      return (*id, name);
+    }
+    let body = results.analysis.body;
+    let ty = place.ty(&body.local_decls, results.analysis.tcx).ty;
+    let id = self.add_node(NodeKind::Place, name.clone(), format!("{ty:?}"), None, None);
+    self.places.insert(place, id);
+    (id, name)
+  }
+
+  fn add_edge(
+    &mut self,
+    src: LocationOrArg,
+    dst: LocationOrArg,
+    kind: EdgeKind,
+    place: Option<String>,
+  ) {
+    let (src, dst) = (self.graph.locations[&src], self.graph.locations[&dst]);
+    self.add_edge_ids(src, dst, kind, place);
+  }
+
+  fn add_edge_ids(
+    &mut self,
+    src: usize,
+    dst: usize,
+    kind: EdgeKind,
+    place: Option<String>,
+  ) {
+    if self.edges.insert((src, dst, kind)) {
+      self.graph.edges.push(DependenceEdge {
+        src,
+        dst,
+        kind,
+        place,
+      });
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::{infoflow, test_utils};
+
+  #[test]
+  fn test_dependence_graph() {
+    let input = r#"
+fn main() {
+  let x = 1;
+  let y = 2;
+  let mut z = x + y;
+  if z > 0 {
+    z = 0;
+  }
+}
+    "#;
+    test_utils::compile_body(input, |tcx, body_id, body_with_facts| {
+      let results = infoflow::compute_flow(tcx, body_id, body_with_facts);
+      let graph = DependenceGraph::build(&results, false);
+
+      let node = |label: &str| {
+        graph
+          .nodes
+          .iter()
+          .find(|node| node.label.contains(label))
+          .unwrap_or_else(|| panic!("no node for {label}"))
+          .id
+      };
+      let has_edge = |src: usize, dst: usize, kind: EdgeKind| {
+        graph
+          .edges
+          .iter()
+          .any(|edge| edge.src == src && edge.dst == dst && edge.kind == kind)
+      };
+      let reaches_by_data = |src: usize, dst: usize| {
+        let mut stack = vec![src];
+        let mut visited = HashSet::default();
+        while let Some(node) = stack.pop() {
+          if node == dst {
+            return true;
+          }
+          if visited.insert(node) {
+            stack.extend(
+              graph
+                .edges
+                .iter()
+                .filter(|edge| edge.src == node && edge.kind == EdgeKind::Data)
+                .map(|edge| edge.dst),
+            );
+          }
+        }
+        false
+      };
+
+      // x + y depends on the writes to x and y, but not directly since they are
+      // copied into temporaries first
+      let add = node("AddWithOverflow");
+      assert!(reaches_by_data(node("const 1_i32"), add));
+      assert!(reaches_by_data(node("const 2_i32"), add));
+      assert!(!has_edge(node("const 1_i32"), add, EdgeKind::Data));
+
+      // z = 0 is control-dependent on the branch, and not data-dependent on x
+      let write_z = graph
+        .nodes
+        .iter()
+        .find(|node| node.snippet.as_deref() == Some("z = 0"))
+        .unwrap()
+        .id;
+      let branch = node("switchInt");
+      assert!(has_edge(branch, write_z, EdgeKind::Control));
+      assert!(!reaches_by_data(node("const 1_i32"), write_z));
+
+      assert!(graph.to_dot().starts_with("digraph \"main\""));
+      let json: serde_json::Value = serde_json::from_str(&graph.to_json()).unwrap();
+      assert_eq!(json["nodes"].as_array().unwrap().len(), graph.nodes.len());
+
+      let with_places = DependenceGraph::build(&results, true);
+      assert!(
+        with_places
+          .nodes
+          .iter()
+          .any(|node| node.kind == NodeKind::Place)
+      );
+    });
+  }
+
+  #[test]
+  fn test_graph_queries() {
+    let input = r#"
+fn main() {
+  let a = 1;
+  let b = f(a);
+  let c = f(b);
+  let d = f(c);
+  let e = 5;
+}
+
+fn f(x: i32) -> i32 { x }
+    "#;
+    test_utils::compile_body(input, |tcx, body_id, body_with_facts| {
+      let results = infoflow::compute_flow(tcx, body_id, body_with_facts);
+      let graph = DependenceGraph::build(&results, false);
+
+      let node = |snippet: &str| {
+        graph
+          .nodes
+          .iter()
+          .find(|node| node.snippet.as_deref() == Some(snippet))
+          .unwrap_or_else(|| panic!("no node for {snippet}"))
+          .id
+      };
+      let (a, b, c, d, e) = (
+        node("1"),
+        node("f(a)"),
+        node("f(b)"),
+        node("f(c)"),
+        node("5"),
+      );
+
+      let location = graph
+        .locations
+        .iter()
+        .find(|(_, id)| **id == d)
+        .map(|(location, _)| *location)
+        .unwrap();
+      assert_eq!(graph.node(location), Some(d));
+
+      let backward = graph.reachable([d], Direction::Backward);
+      assert!([a, b, c, d].iter().all(|node| backward.contains(node)));
+      assert!(!backward.contains(&e));
+      let forward = graph.reachable([a], Direction::Forward);
+      assert!(forward.contains(&d) && !forward.contains(&e));
+
+      let path = graph.shortest_path(a, d).unwrap();
+      assert_eq!(path.first(), Some(&a));
+      assert_eq!(path.last(), Some(&d));
+      let position = |node| path.iter().position(|n| *n == node).unwrap();
+      assert!(position(b) < position(c));
+      assert_eq!(graph.shortest_path(d, a), None);
+      assert_eq!(graph.shortest_path(e, d), None);
+
+      let dominators = graph.dominators(d);
+      let position = |node| dominators.iter().position(|n| *n == node).unwrap();
+      assert!(position(c) < position(b) && position(b) < position(a));
+
+      let slice = graph.subgraph(&backward);
+      assert_eq!(slice.nodes.len(), backward.len());
+      assert!(
+        slice
+          .nodes
+          .iter()
+          .all(|node| node.snippet.as_deref() != Some("5"))
+      );
+      let slice_d = slice.node(location).unwrap();
+      assert_eq!(slice.nodes[slice_d].snippet.as_deref(), Some("f(c)"));
+      assert!(slice.edges.iter().any(|edge| edge.dst == slice_d));
+
+      assert!(
+        graph
+          .neighbors(d, Direction::Backward)
+          .filter(|edge| edge.kind == EdgeKind::Data)
+          .all(|edge| edge.place.is_some())
+      );
+      let data_only = graph.filter_edges(|edge| edge.kind == EdgeKind::Data);
+      assert!(
+        data_only
+          .edges
+          .iter()
+          .all(|edge| edge.kind == EdgeKind::Data)
+      );
+      for graph in [&graph, &data_only, &slice] {
+        for node in 0 .. graph.nodes.len() {
+          let touching = graph
+            .edges
+            .iter()
+            .filter(|edge| edge.src == node || edge.dst == node)
+            .count();
+          assert_eq!(graph.neighbors(node, Direction::Both).count(), touching);
+        }
+      }
+    });
+  }
+}
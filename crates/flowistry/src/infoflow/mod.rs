@@ -13,14 +13,23 @@ use rustc_utils::{BodyExt, block_timer};
 
 pub use self::{
   analysis::{FlowAnalysis, FlowDomain},
-  dependencies::{Direction, compute_dependencies, compute_dependency_spans},
+  dependencies::{
+    Direction, compute_dependencies, compute_dependencies_by_certainty,
+    compute_dependency_spans, dependency_spans,
+  },
 };
 use crate::mir::{engine, placeinfo::PlaceInfo};
 
 mod analysis;
+pub mod caveats;
+#[cfg(feature = "debug")]
+pub mod debug;
 mod dependencies;
+pub mod facts;
+pub mod graph;
 pub mod mutation;
 mod recursive;
+pub mod summaries;
 
 /// The output of the information flow analysis.
 ///
@@ -80,6 +89,32 @@ pub fn compute_flow<'a, 'tcx>(
   tcx: TyCtxt<'tcx>,
   body_id: BodyId,
   body_with_facts: &'a BodyWithBorrowckFacts<'tcx>,
+) -> FlowResults<'a, 'tcx> {
+  compute_flow_inner(tcx, body_id, body_with_facts, false)
+}
+
+/// Computes information flow for a MIR body, ignoring mutations that only
+/// [possibly](mutation::MutationStatus::Possibly) happen, such as an opaque call `f(&mut x)`.
+///
+/// The dependencies in the returned results are those established through definite
+/// mutations alone. Comparing them against the dependencies of [`compute_flow`]
+/// distinguishes definite from speculative dependencies, see [`compute_dependencies_by_certainty`].
+///
+/// This runs the whole analysis again, so it is only worth calling if
+/// [`FlowAnalysis::has_possible_mutations`] is true for the results of [`compute_flow`].
+pub fn compute_definite_flow<'a, 'tcx>(
+  tcx: TyCtxt<'tcx>,
+  body_id: BodyId,
+  body_with_facts: &'a BodyWithBorrowckFacts<'tcx>,
+) -> FlowResults<'a, 'tcx> {
+  compute_flow_inner(tcx, body_id, body_with_facts, true)
+}
+
+pub(crate) fn compute_flow_inner<'a, 'tcx>(
+  tcx: TyCtxt<'tcx>,
+  body_id: BodyId,
+  body_with_facts: &'a BodyWithBorrowckFacts<'tcx>,
+  definite_only: bool,
 ) -> FlowResults<'a, 'tcx> {
   BODY_STACK.with(|body_stack| {
     body_stack.borrow_mut().push(body_id);
@@ -94,7 +129,8 @@ pub fn compute_flow<'a, 'tcx>(
     let results = {
       block_timer!("Flow");
 
-      let analysis = FlowAnalysis::new(tcx, def_id, body, place_info);
+      let analysis = FlowAnalysis::new(tcx, def_id, body, place_info)
+        .with_definite_only(definite_only);
       engine::iterate_to_fixpoint(tcx, body, location_domain, analysis)
       // analysis.into_engine(tcx, body).iterate_to_fixpoint()
     };
@@ -121,11 +157,20 @@ pub fn compute_flow<'a, 'tcx>(
       );
     }
 
-    if std::env::var("DUMP_MIR").is_ok()
+    if let Ok(dir) = std::env::var("DUMP_MIR")
       && BODY_STACK.with(|body_stack| body_stack.borrow().len() == 1)
     {
-      todo!()
-      // utils::dump_results(body, &results, def_id, tcx).unwrap();
+      cfg_if::cfg_if! {
+        if #[cfg(feature = "debug")] {
+          match debug::dump_results(&results, std::path::Path::new(&dir)) {
+            Ok(path) => log::info!("Wrote flow results to {}", path.display()),
+            Err(err) => log::warn!("Failed to dump flow results: {err:?}"),
+          }
+        } else {
+          let _ = dir;
+          log::warn!("DUMP_MIR requires Flowistry to be built with the `debug` feature");
+        }
+      }
     }
 
     body_stack.borrow_mut().pop();
@@ -8,6 +8,7 @@ use rustc_middle::{
 };
 use rustc_utils::{AdtDefExt, OperandExt, mir::place::PlaceCollector};
 
+use super::summaries::{self, FunctionSummary};
 use crate::mir::{
   placeinfo::PlaceInfo,
   utils::{self, AsyncHack},
@@ -184,11 +185,38 @@ where
 
     match &terminator.kind {
       TerminatorKind::Call {
-        /*func,*/ // TODO: deal with func
+        func,
         args,
         destination,
         ..
       } => {
+        if let Some(summary) = summaries::summary_for_call(tcx, func) {
+          debug!("  Using user-provided summary {summary:?}");
+          let mut mutations = vec![Mutation {
+            mutated: *destination,
+            inputs: FunctionSummary::arg_places(args, &summary.return_inputs),
+            status: MutationStatus::Definitely,
+          }];
+
+          for mutation in &summary.mutations {
+            let Some(arg) = args.get(mutation.arg).and_then(|arg| arg.node.as_place())
+            else {
+              continue;
+            };
+            let inputs = FunctionSummary::arg_places(args, &mutation.inputs);
+            for arg_mut in self.place_info.reachable_values(arg, Mutability::Mut) {
+              mutations.push(Mutation {
+                mutated: *arg_mut,
+                inputs: inputs.clone(),
+                status: MutationStatus::Possibly,
+              });
+            }
+          }
+
+          (self.f)(location, mutations);
+          return;
+        }
+
         let async_hack = AsyncHack::new(
           self.place_info.tcx,
           self.place_info.body,
@@ -217,8 +245,8 @@ where
           status: MutationStatus::Definitely,
         }];
 
-        for arg in arg_places {
-          for arg_mut in self.place_info.reachable_values(arg, Mutability::Mut) {
+        for arg in &arg_places {
+          for arg_mut in self.place_info.reachable_values(*arg, Mutability::Mut) {
             mutations.push(Mutation {
               mutated: *arg_mut,
               inputs: arg_inputs.clone(),
@@ -227,6 +255,29 @@ where
           }
         }
 
+        // Globals with interior mutability can be mutated through a shared reference,
+        // e.g. `CONFIG.set(..)` for a `static CONFIG: OnceLock<_>` or `KEY.with(..)` for
+        // a thread-local.
+        let globals = self.place_info.globals();
+        for arg in &arg_places {
+          let arg_ty = arg.ty(self.place_info.body.local_decls(), tcx).ty;
+          if !arg_ty.is_ref() {
+            continue;
+          }
+          for alias in self.place_info.aliases(tcx.mk_place_deref(*arg)) {
+            if globals
+              .global_of(*alias)
+              .is_some_and(|def_id| globals.is_interior_mutable(def_id))
+            {
+              mutations.push(Mutation {
+                mutated: *alias,
+                inputs: arg_inputs.clone(),
+                status: MutationStatus::Possibly,
+              });
+            }
+          }
+        }
+
         (self.f)(location, mutations);
       }
 
@@ -1,6 +1,7 @@
 #![allow(unused)]
 
 use log::{debug, info};
+use rustc_hir::{self as hir, intravisit};
 use rustc_middle::{
   mir::*,
   ty::{ClosureKind, GenericArgKind, TyKind},
@@ -8,7 +9,12 @@ use rustc_middle::{
 use rustc_mir_dataflow::JoinSemiLattice;
 use rustc_utils::{PlaceExt, mir::borrowck_facts::get_body_with_borrowck_facts};
 
-use super::{BODY_STACK, analysis::FlowAnalysis};
+use super::{
+  BODY_STACK,
+  analysis::FlowAnalysis,
+  caveats::{Caveat, CaveatKind},
+  summaries,
+};
 use crate::{
   extensions::REACHED_LIBRARY,
   infoflow::{
@@ -37,6 +43,13 @@ impl<'tcx> FlowAnalysis<'_, 'tcx> {
     };
     debug!("Checking whether can recurse into {func:?}");
 
+    // User-provided summaries take precedence over the callee's body, and are
+    // applied by the ModularMutationVisitor.
+    if summaries::summary_for_call(tcx, func).is_some() {
+      debug!("  Func has a user-provided summary");
+      return false;
+    }
+
     let func = match func.constant() {
       Some(func) => func,
       None => {
@@ -65,6 +78,15 @@ impl<'tcx> FlowAnalysis<'_, 'tcx> {
       Some(node) => node,
       None => {
         debug!("  Func is not in local crate");
+        self.caveats.add(
+          CaveatKind::ReachedLibrary,
+          location,
+          self.body,
+          format!(
+            "`{}` is defined in another crate, so its effects are approximated from its signature",
+            tcx.def_path_str(*def_id)
+          ),
+        );
         REACHED_LIBRARY.get(|reached_library| {
           if let Some(reached_library) = reached_library {
             *reached_library.borrow_mut() = true;
@@ -88,6 +110,8 @@ impl<'tcx> FlowAnalysis<'_, 'tcx> {
     //   debug!("  Func contains unsafe blocks");
     //   return false;
     // }
+    let callee_unsafe = fn_sig.skip_binder().safety().is_unsafe()
+      || contains_unsafe_block(tcx.hir_body(body_id));
 
     let parent_arg_places = utils::arg_places(parent_args);
     let any_closure_inputs = parent_arg_places.iter().any(|(_, place)| {
@@ -121,10 +145,37 @@ impl<'tcx> FlowAnalysis<'_, 'tcx> {
     let mut recurse_cache = self.recurse_cache.borrow_mut();
     let flow = recurse_cache.entry(body_id).or_insert_with(|| {
       info!("Recursing into {}", tcx.def_path_debug_str(*def_id));
-      super::compute_flow(tcx, body_id, body_with_facts)
+      super::compute_flow_inner(tcx, body_id, body_with_facts, self.definite_only)
     });
+    if flow.analysis.has_possible_mutations() {
+      self.possible_mutations.set(true);
+    }
     let body = &body_with_facts.body;
 
+    if callee_unsafe {
+      self.caveats.add(
+        CaveatKind::UnsafeCallee,
+        location,
+        self.body,
+        format!(
+          "`{}` contains unsafe code, whose effects through raw pointers may be missed",
+          tcx.def_path_str(*def_id)
+        ),
+      );
+    }
+    let callee = tcx.def_path_str(*def_id);
+    for Caveat {
+      kind, description, ..
+    } in flow.analysis.caveats()
+    {
+      self.caveats.add(
+        kind,
+        location,
+        self.body,
+        format!("in `{callee}`: {description}"),
+      );
+    }
+
     let mut return_state = FlowDomain::new(flow.analysis.location_domain());
     {
       let return_locs = body
@@ -148,6 +199,12 @@ impl<'tcx> FlowAnalysis<'_, 'tcx> {
         return None;
       }
 
+      // Places within a global are translated to the same place through the parent's
+      // pointer to that global, if the parent refers to it.
+      if let Some(def_id) = flow.analysis.place_info.globals().global_of(child) {
+        return self.place_info.globals().translate(def_id, child);
+      }
+
       let can_translate = child.local == RETURN_PLACE
         || (child.is_arg(body) && (!mutated || child.is_indirect()));
       if !can_translate {
@@ -238,3 +295,21 @@ impl<'tcx> FlowAnalysis<'_, 'tcx> {
     true
   }
 }
+
+fn contains_unsafe_block(body: &hir::Body<'_>) -> bool {
+  struct UnsafeBlockFinder(bool);
+  impl<'tcx> intravisit::Visitor<'tcx> for UnsafeBlockFinder {
+    fn visit_block(&mut self, block: &'tcx hir::Block<'tcx>) {
+      if let hir::BlockCheckMode::UnsafeBlock(hir::UnsafeSource::UserProvided) =
+        block.rules
+      {
+        self.0 = true;
+      }
+      intravisit::walk_block(self, block);
+    }
+  }
+
+  let mut finder = UnsafeBlockFinder(false);
+  intravisit::Visitor::visit_body(&mut finder, body);
+  finder.0
+}
@@ -0,0 +1,240 @@
+//! User-provided flow summaries for functions that Flowistry cannot (or should not) analyze.
+//!
+//! By default, a call to a function is approximated by its type signature (see
+//! [`ModularMutationVisitor`](super::mutation::ModularMutationVisitor)), and under
+//! [`ContextMode::Recurse`](crate::extensions::ContextMode::Recurse) Flowistry can only
+//! recurse into functions defined in the local crate. A summary file lets users declare the
+//! flows of a function once, e.g. for FFI wrappers or third-party crates.
+//!
+//! A summary file is a JSON object that maps function paths, as printed by
+//! [`TyCtxt::def_path_str`] but without generic arguments, to a [`FunctionSummary`]. For example,
+//! `Vec::insert` is written `std::vec::Vec::insert` rather than `std::vec::Vec::<T, A>::insert`,
+//! and generic arguments in keys are ignored. Arguments are numbered from 0. For example:
+//!
+//! ```json
+//! {
+//!   "serde_json::to_string": { "return": [0] },
+//!   "std::mem::replace": {
+//!     "return": [0],
+//!     "mutations": [{ "arg": 0, "inputs": [1] }]
+//!   }
+//! }
+//! ```
+//!
+//! Summaries are enabled by setting [`FLOW_SUMMARIES`] with [`fluid_set`](fluid_let::fluid_set).
+//! Tools that analyze a whole crate can call [`FlowSummaries::warn_unused`] afterwards to log
+//! the summaries that never applied to a call, which are likely mistyped.
+
+use std::{cell::RefCell, fs, path::Path};
+
+use anyhow::{Context, Result};
+use fluid_let::fluid_let;
+use rustc_data_structures::fx::{FxHashMap as HashMap, FxHashSet as HashSet};
+use rustc_hir::def_id::DefId;
+use rustc_middle::{
+  mir::{Operand, Place},
+  ty::{TyCtxt, TyKind},
+};
+use rustc_span::source_map::Spanned;
+use rustc_utils::OperandExt;
+use serde::{Deserialize, Serialize};
+
+/// The default name of a summary file, relative to the workspace root.
+pub const DEFAULT_SUMMARIES_FILE: &str = "flowistry-summaries.json";
+
+/// The flows of a single function.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionSummary {
+  /// Arguments whose values flow into the return value.
+  #[serde(default, rename = "return")]
+  pub return_inputs: Vec<usize>,
+
+  /// Pointees of arguments that may be mutated by the function.
+  #[serde(default)]
+  pub mutations: Vec<MutationSummary>,
+}
+
+/// A mutation to the data reachable from an argument.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MutationSummary {
+  /// The argument whose mutable pointees are mutated.
+  pub arg: usize,
+
+  /// Arguments whose values flow into the mutated data.
+  #[serde(default)]
+  pub inputs: Vec<usize>,
+}
+
+/// A collection of [`FunctionSummary`]s indexed by function path.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(
+  from = "HashMap<String, FunctionSummary>",
+  into = "HashMap<String, FunctionSummary>"
+)]
+pub struct FlowSummaries {
+  /// The summaries by path, without generic arguments.
+  functions: HashMap<String, FunctionSummary>,
+
+  /// The paths of the summaries that applied to a call.
+  used: RefCell<HashSet<String>>,
+}
+
+impl From<HashMap<String, FunctionSummary>> for FlowSummaries {
+  fn from(functions: HashMap<String, FunctionSummary>) -> Self {
+    let mut summaries = FlowSummaries::default();
+    for (path, summary) in functions {
+      summaries.insert(path, summary);
+    }
+    summaries
+  }
+}
+
+impl From<FlowSummaries> for HashMap<String, FunctionSummary> {
+  fn from(summaries: FlowSummaries) -> Self {
+    summaries.functions
+  }
+}
+
+/// Removes the generic arguments from a path, e.g. `std::vec::Vec::<T, A>::insert` becomes
+/// `std::vec::Vec::insert`. The angle brackets of qualified paths like `<T as Trait>::f` are
+/// kept.
+fn strip_generic_args(path: &str) -> String {
+  let mut stripped = String::with_capacity(path.len());
+  let mut depth = 0;
+  for c in path.chars() {
+    if depth > 0 {
+      match c {
+        '<' => depth += 1,
+        '>' => depth -= 1,
+        _ => {}
+      }
+      continue;
+    }
+    let before_turbofish = stripped.strip_suffix("::").unwrap_or(&stripped);
+    let follows_name = before_turbofish
+      .chars()
+      .last()
+      .is_some_and(|c| c.is_alphanumeric() || c == '_');
+    if c == '<' && follows_name {
+      stripped.truncate(before_turbofish.len());
+      depth = 1;
+    } else {
+      stripped.push(c);
+    }
+  }
+  stripped
+}
+
+impl FlowSummaries {
+  /// Parses summaries from a JSON string.
+  pub fn parse(contents: &str) -> Result<Self> {
+    serde_json::from_str(contents).context("Failed to parse flow summaries")
+  }
+
+  /// Reads and parses summaries from a JSON file.
+  pub fn load(path: &Path) -> Result<Self> {
+    let contents = fs::read_to_string(path).with_context(|| {
+      format!("Failed to read flow summaries from {}", path.display())
+    })?;
+    Self::parse(&contents)
+  }
+
+  /// Adds (or replaces) the summary for a function path.
+  pub fn insert(&mut self, path: impl AsRef<str>, summary: FunctionSummary) {
+    self
+      .functions
+      .insert(strip_generic_args(path.as_ref()), summary);
+  }
+
+  /// Returns the summary for the function `def_id`, if one exists.
+  pub fn get(&self, tcx: TyCtxt<'_>, def_id: DefId) -> Option<&FunctionSummary> {
+    if self.functions.is_empty() {
+      return None;
+    }
+    let path = strip_generic_args(&tcx.def_path_str(def_id));
+    let summary = self.functions.get(&path)?;
+    self.used.borrow_mut().insert(path);
+    Some(summary)
+  }
+
+  /// Logs a warning for each summary that has not applied to a call.
+  pub fn warn_unused(&self) {
+    let used = self.used.borrow();
+    let mut unused = self
+      .functions
+      .keys()
+      .filter(|path| !used.contains(*path))
+      .collect::<Vec<_>>();
+    unused.sort();
+    for path in unused {
+      log::warn!("The flow summary for `{path}` did not apply to any call");
+    }
+  }
+}
+
+fluid_let! {
+  /// The summaries used by the analysis for the current scope.
+  pub static FLOW_SUMMARIES: FlowSummaries
+}
+
+/// Returns the summary for the callee of a function call, if one has been provided
+/// via [`FLOW_SUMMARIES`].
+pub(crate) fn summary_for_call<'tcx>(
+  tcx: TyCtxt<'tcx>,
+  func: &Operand<'tcx>,
+) -> Option<FunctionSummary> {
+  let constant = func.constant()?;
+  let TyKind::FnDef(def_id, _) = constant.const_.ty().kind() else {
+    return None;
+  };
+  FLOW_SUMMARIES.get(|summaries| summaries?.get(tcx, *def_id).cloned())
+}
+
+impl FunctionSummary {
+  /// Returns the places of the given argument indices, skipping arguments that are constants.
+  pub(crate) fn arg_places<'tcx>(
+    args: &[Spanned<Operand<'tcx>>],
+    indices: &[usize],
+  ) -> Vec<Place<'tcx>> {
+    indices
+      .iter()
+      .filter_map(|i| args.get(*i)?.node.as_place())
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_strip_generic_args() {
+    for (path, expected) in [
+      ("std::mem::replace", "std::mem::replace"),
+      ("std::vec::Vec::<T, A>::insert", "std::vec::Vec::insert"),
+      (
+        "std::collections::HashMap::<K, V, S>::insert",
+        "std::collections::HashMap::insert",
+      ),
+      ("std::iter::repeat::<T>", "std::iter::repeat"),
+      (
+        "<std::vec::Vec<T, A> as std::ops::Drop>::drop",
+        "<std::vec::Vec as std::ops::Drop>::drop",
+      ),
+      ("Foo::<Vec<u8>>::bar", "Foo::bar"),
+    ] {
+      assert_eq!(strip_generic_args(path), expected);
+    }
+  }
+
+  #[test]
+  fn test_parse_strips_generic_args() {
+    let summaries = FlowSummaries::parse(
+      r#"{"std::vec::Vec::<T, A>::insert": {"return": [0]}, "std::mem::take": {}}"#,
+    )
+    .unwrap();
+    let mut paths = summaries.functions.keys().cloned().collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(paths, ["std::mem::take", "std::vec::Vec::insert"]);
+  }
+}
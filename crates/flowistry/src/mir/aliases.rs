@@ -22,7 +22,10 @@ use rustc_utils::{PlaceExt, mir::place::UNKNOWN_REGION, timer::elapsed};
 
 use crate::{
   extensions::{PointerMode, is_extension_active},
-  mir::utils::{AsyncHack, PlaceSet},
+  mir::{
+    globals::Globals,
+    utils::{AsyncHack, PlaceSet},
+  },
 };
 
 type BorrowckLocationIndex =
@@ -52,6 +55,20 @@ impl<'tcx> Visitor<'tcx> for GatherBorrows<'tcx> {
   }
 }
 
+/// The maximum number of projections of a pointer within an argument whose pointee
+/// is tracked, e.g. `(*x.0).1` has two.
+///
+/// Functions with many pointers in their inputs would otherwise generate a huge number
+/// of loans. This is almost certainly not sound, so every argument that hits the limit
+/// is reported through [`Aliases::pointer_limited_args`].
+pub const MAX_ARG_POINTER_PROJECTIONS: usize = 2;
+
+/// Returns true if the pointee of `ptr`, a pointer within an argument, is tracked by the
+/// alias analysis. See [`MAX_ARG_POINTER_PROJECTIONS`].
+pub(crate) fn is_tracked_arg_pointer(ptr: Place<'_>) -> bool {
+  ptr.projection.len() <= MAX_ARG_POINTER_PROJECTIONS
+}
+
 type LoanSet<'tcx> = HashSet<(Place<'tcx>, Mutability)>;
 type LoanMap<'tcx> = HashMap<RegionVid, LoanSet<'tcx>>;
 
@@ -60,6 +77,7 @@ pub struct Aliases<'a, 'tcx> {
   tcx: TyCtxt<'tcx>,
   body: &'a Body<'tcx>,
   pub(super) loans: LoanMap<'tcx>,
+  pointer_limited_args: Vec<Local>,
 }
 
 rustc_index::newtype_index! {
@@ -74,12 +92,15 @@ impl<'a, 'tcx> Aliases<'a, 'tcx> {
     tcx: TyCtxt<'tcx>,
     def_id: DefId,
     body_with_facts: &'a BodyWithBorrowckFacts<'tcx>,
+    globals: &Globals<'tcx>,
   ) -> Self {
-    let loans = Self::compute_loans(tcx, def_id, body_with_facts, |_, _, _| true);
+    let (loans, pointer_limited_args) =
+      Self::compute_loans(tcx, def_id, body_with_facts, globals, |_, _, _| true);
     Aliases {
       tcx,
       body: &body_with_facts.body,
       loans,
+      pointer_limited_args,
     }
   }
 
@@ -90,22 +111,32 @@ impl<'a, 'tcx> Aliases<'a, 'tcx> {
     tcx: TyCtxt<'tcx>,
     def_id: DefId,
     body_with_facts: &'a BodyWithBorrowckFacts<'tcx>,
+    globals: &Globals<'tcx>,
     selector: impl Fn(RegionVid, RegionVid, BorrowckLocationIndex) -> bool,
   ) -> Self {
-    let loans = Self::compute_loans(tcx, def_id, body_with_facts, selector);
+    let (loans, pointer_limited_args) =
+      Self::compute_loans(tcx, def_id, body_with_facts, globals, selector);
     Aliases {
       tcx,
       body: &body_with_facts.body,
       loans,
+      pointer_limited_args,
     }
   }
 
+  /// Returns the arguments containing pointers nested too deeply to be tracked,
+  /// see [`MAX_ARG_POINTER_PROJECTIONS`].
+  pub fn pointer_limited_args(&self) -> &[Local] {
+    &self.pointer_limited_args
+  }
+
   fn compute_loans(
     tcx: TyCtxt<'tcx>,
     def_id: DefId,
     body_with_facts: &'a BodyWithBorrowckFacts<'tcx>,
+    globals: &Globals<'tcx>,
     constraint_selector: impl Fn(RegionVid, RegionVid, BorrowckLocationIndex) -> bool,
-  ) -> LoanMap<'tcx> {
+  ) -> (LoanMap<'tcx>, Vec<Local>) {
     let start = Instant::now();
     let body = &body_with_facts.body;
     let static_region = RegionVid::from_usize(0);
@@ -208,23 +239,35 @@ impl<'a, 'tcx> Aliases<'a, 'tcx> {
     }
 
     // For all args p : &'a ω T where 'a is abstract: contains('a, *p, ω).
+    let mut pointer_limited_args = Vec::new();
     for arg in body.args_iter() {
       for (region, places) in
         Place::from_local(arg, tcx).interior_pointers(tcx, body, def_id)
       {
         let region_contains = contains.entry(region).or_default();
         for (place, mutability) in places {
-          // WARNING / TODO: this is a huge hack (that is conjoined w/ all_args).
-          // Need a way to limit the number of possible pointers for functions with
-          // many pointers in the input. This is almost certainly not sound, but hopefully
-          // it works for most cases.
-          if place.projection.len() <= 2 {
+          // WARNING / TODO: this limit is shared with PlaceInfo::all_args.
+          if is_tracked_arg_pointer(place) {
             region_contains.insert((tcx.mk_place_deref(place), mutability));
+          } else if pointer_limited_args.last() != Some(&arg) {
+            pointer_limited_args.push(arg);
           }
         }
       }
     }
 
+    // For all pointers to globals p : &'a ω T: contains('a, *p, ω).
+    for (local, _) in globals.pointers() {
+      if let TyKind::Ref(region, _, mutability) = body.local_decls()[local].ty.kind()
+        && let RegionKind::ReVar(region) = region.kind()
+      {
+        contains.entry(region).or_default().insert((
+          tcx.mk_place_deref(Place::from_local(local, tcx)),
+          *mutability,
+        ));
+      }
+    }
+
     // For all places p : *T or p : Box<T>: contains('UNK, *p, mut).
     let unk_contains = contains.entry(UNKNOWN_REGION).or_default();
     for (region, places) in &all_pointers {
@@ -348,7 +391,7 @@ impl<'a, 'tcx> Aliases<'a, 'tcx> {
 
     log::trace!("contains: {contains:#?}");
 
-    contains
+    (contains, pointer_limited_args)
   }
 
   /// Given a `place`, returns the set of direct places it could refer to.
@@ -473,7 +516,8 @@ mod test {
     test_utils::compile_body(input, |tcx, body_id, body_with_facts| {
       let body = &body_with_facts.body;
       let def_id = tcx.hir_body_owner_def_id(body_id);
-      let aliases = Aliases::build(tcx, def_id.to_def_id(), body_with_facts);
+      let globals = Globals::build(tcx, body_with_facts);
+      let aliases = Aliases::build(tcx, def_id.to_def_id(), body_with_facts, &globals);
 
       f(tcx, body, aliases)
     });
@@ -540,4 +584,16 @@ fn main() {
       );
     });
   }
+
+  #[test]
+  fn test_aliases_pointer_limit() {
+    let input = r#"
+fn main(x: &&&&i32, y: &&i32) {}
+    "#;
+    alias_harness(input, |_, body, aliases| {
+      // `***x` has more than `MAX_ARG_POINTER_PROJECTIONS` projections, so it is not tracked
+      let x = body.args_iter().next().unwrap();
+      assert_eq!(aliases.pointer_limited_args(), &[x]);
+    });
+  }
 }
@@ -0,0 +1,198 @@
+//! Identifies references to global state within a body.
+//!
+//! A static item `X` is accessed in MIR through a pointer constant, e.g. `_2 = const {alloc1: &i32}`
+//! followed by a read of `(*_2)`. A thread-local declared with `thread_local!` is accessed through
+//! a promoted reference to its `LocalKey`, e.g. `_15 = const main::promoted[0]`, and a `#[thread_local]`
+//! static through `Rvalue::ThreadLocalRef`. [`Globals`] maps each of these pointer locals back
+//! to the global it points to.
+//!
+//! Every global referenced in a body is then modeled like an argument: its value is an input at
+//! function entry, and mutations to it are visible at function exit. Because the same global can be
+//! accessed through many different pointer locals, each global is represented by the first local that
+//! points to it, and all of its pointer locals alias one another.
+
+use rustc_borrowck::consumers::BodyWithBorrowckFacts;
+use rustc_data_structures::fx::{FxHashMap as HashMap, FxHashSet as HashSet, FxIndexMap};
+use rustc_hir::def_id::DefId;
+use rustc_middle::{
+  mir::{
+    interpret::{GlobalAlloc, Scalar},
+    *,
+  },
+  ty::{self, Ty, TyCtxt},
+};
+use rustc_span::sym;
+use rustc_utils::PlaceExt;
+
+/// The globals referenced by a body, and the locals that point to them.
+pub struct Globals<'tcx> {
+  tcx: TyCtxt<'tcx>,
+  pointers: HashMap<Local, DefId>,
+  locals: FxIndexMap<DefId, Vec<Local>>,
+  interior_mutable: HashSet<DefId>,
+}
+
+impl<'tcx> Globals<'tcx> {
+  /// Finds all pointers to globals in a body.
+  pub fn build(tcx: TyCtxt<'tcx>, body_with_facts: &BodyWithBorrowckFacts<'tcx>) -> Self {
+    let body = &body_with_facts.body;
+    let mut pointers = HashMap::default();
+    let mut ambiguous = HashSet::default();
+    let mut interior_mutable = HashSet::default();
+
+    for data in body.basic_blocks.iter() {
+      for statement in &data.statements {
+        let StatementKind::Assign(box (place, rvalue)) = &statement.kind else {
+          continue;
+        };
+        let Some(local) = place.as_local() else {
+          continue;
+        };
+
+        let global = match rvalue {
+          Rvalue::Use(Operand::Constant(constant)) => {
+            global_of_constant(tcx, constant, body_with_facts)
+          }
+          Rvalue::ThreadLocalRef(def_id) => Some((*def_id, false)),
+          _ => None,
+        };
+
+        let Some((def_id, is_key)) = global else {
+          continue;
+        };
+        if is_key || !static_is_freeze(tcx, def_id) {
+          interior_mutable.insert(def_id);
+        }
+        if *pointers.entry(local).or_insert(def_id) != def_id {
+          ambiguous.insert(local);
+        }
+      }
+    }
+
+    for local in ambiguous {
+      pointers.remove(&local);
+    }
+
+    let mut sorted = pointers.iter().map(|(l, d)| (*l, *d)).collect::<Vec<_>>();
+    sorted.sort_by_key(|(local, _)| *local);
+    let mut locals = FxIndexMap::<DefId, Vec<Local>>::default();
+    for (local, def_id) in sorted {
+      locals.entry(def_id).or_default().push(local);
+    }
+
+    Globals {
+      tcx,
+      pointers,
+      locals,
+      interior_mutable,
+    }
+  }
+
+  /// Returns the global that `place` refers to, if `place` is a dereference
+  /// of a pointer to a global.
+  pub fn global_of(&self, place: Place<'tcx>) -> Option<DefId> {
+    match place.projection.first() {
+      Some(ProjectionElem::Deref) => self.pointers.get(&place.local).copied(),
+      _ => None,
+    }
+  }
+
+  /// Returns each local that points to a global, along with that global.
+  pub fn pointers(&self) -> impl Iterator<Item = (Local, DefId)> + '_ {
+    self
+      .pointers
+      .iter()
+      .map(|(local, def_id)| (*local, *def_id))
+  }
+
+  /// Returns each global referenced in the body along with the local that represents it.
+  pub fn representatives(&self) -> impl Iterator<Item = (DefId, Local)> + '_ {
+    self
+      .locals
+      .iter()
+      .map(|(def_id, locals)| (*def_id, locals[0]))
+  }
+
+  /// Re-roots a place within a global onto this body's representative of that global, if any.
+  ///
+  /// For example, if `place` is `(*_3).0` in another body where `_3` points to `X`,
+  /// and `_5` represents `X` in this body, then the result is `(*_5).0`.
+  pub fn translate(&self, def_id: DefId, place: Place<'tcx>) -> Option<Place<'tcx>> {
+    let local = self.locals.get(&def_id)?[0];
+    Some(Place::make(local, place.projection, self.tcx))
+  }
+
+  /// Returns the places that refer to the same memory as `place` through
+  /// other pointers to the same global.
+  pub fn siblings(&self, place: Place<'tcx>) -> impl Iterator<Item = Place<'tcx>> + '_ {
+    let locals = match self.global_of(place) {
+      Some(def_id) => self.locals[&def_id].as_slice(),
+      None => &[],
+    };
+    locals
+      .iter()
+      .filter(move |local| **local != place.local)
+      .map(move |local| Place::make(*local, place.projection, self.tcx))
+  }
+
+  /// Returns true if the global can be mutated through a shared reference,
+  /// e.g. a `static` containing a `Cell` or `OnceLock`, or a thread-local.
+  pub fn is_interior_mutable(&self, def_id: DefId) -> bool {
+    self.interior_mutable.contains(&def_id)
+  }
+}
+
+/// Returns the global that a constant points to, and whether the global is a thread-local key.
+fn global_of_constant<'tcx>(
+  tcx: TyCtxt<'tcx>,
+  constant: &ConstOperand<'tcx>,
+  body_with_facts: &BodyWithBorrowckFacts<'tcx>,
+) -> Option<(DefId, bool)> {
+  match constant.const_ {
+    Const::Val(ConstValue::Scalar(Scalar::Ptr(ptr, _)), _) => {
+      match tcx.try_get_global_alloc(ptr.provenance.alloc_id())? {
+        GlobalAlloc::Static(def_id) => Some((def_id, false)),
+        _ => None,
+      }
+    }
+
+    // References to a `thread_local!` key are promoted, so the key is found in the promoted body.
+    Const::Unevaluated(uv, ty) => {
+      let promoted = uv.promoted?;
+      let pointee = ty.builtin_deref(true)?;
+      if !is_local_key(tcx, pointee) {
+        return None;
+      }
+      let promoted_body = body_with_facts.promoted.get(promoted)?;
+      promoted_body.basic_blocks.iter().find_map(|data| {
+        data.statements.iter().find_map(|statement| {
+          let StatementKind::Assign(box (_, Rvalue::Use(Operand::Constant(key)))) =
+            &statement.kind
+          else {
+            return None;
+          };
+          match key.const_ {
+            Const::Unevaluated(uv, ty)
+              if uv.promoted.is_none() && is_local_key(tcx, ty) =>
+            {
+              Some((uv.def, true))
+            }
+            _ => None,
+          }
+        })
+      })
+    }
+
+    _ => None,
+  }
+}
+
+fn is_local_key<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+  ty.ty_adt_def()
+    .is_some_and(|adt_def| tcx.is_diagnostic_item(sym::LocalKey, adt_def.did()))
+}
+
+fn static_is_freeze(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+  let ty = tcx.type_of(def_id).instantiate_identity();
+  ty.is_freeze(tcx, ty::TypingEnv::fully_monomorphized())
+}
@@ -2,5 +2,7 @@
 
 pub mod aliases;
 pub mod engine;
+pub mod globals;
 pub mod placeinfo;
+pub mod provenance;
 pub mod utils;
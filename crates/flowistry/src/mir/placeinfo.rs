@@ -1,6 +1,6 @@
 //! Utilities for analyzing places: children, aliases, etc.
 
-use std::{ops::ControlFlow, rc::Rc};
+use std::{cell::OnceCell, ops::ControlFlow, rc::Rc};
 
 use indexical::ToIndex;
 use rustc_borrowck::consumers::BodyWithBorrowckFacts;
@@ -23,8 +23,16 @@ use rustc_utils::{
   },
 };
 
-use super::{aliases::Aliases, utils::PlaceSet};
-use crate::extensions::{MutabilityMode, is_extension_active};
+use super::{
+  aliases::{Aliases, is_tracked_arg_pointer},
+  globals::Globals,
+  provenance::FlowSensitiveAliases,
+  utils::PlaceSet,
+};
+use crate::{
+  extensions::{MutabilityMode, PointerMode, is_extension_active},
+  infoflow::caveats::{Caveat, CaveatKind},
+};
 
 /// Utilities for analyzing places: children, aliases, etc.
 pub struct PlaceInfo<'a, 'tcx> {
@@ -35,6 +43,12 @@ pub struct PlaceInfo<'a, 'tcx> {
 
   // Core computed data structure
   aliases: Aliases<'a, 'tcx>,
+  globals: Globals<'tcx>,
+  caveats: Vec<Caveat>,
+
+  // Flow-sensitive refinement of aliases, computed on demand
+  body_with_facts: &'a BodyWithBorrowckFacts<'tcx>,
+  flow_aliases: OnceCell<FlowSensitiveAliases<'tcx>>,
 
   // Caching for derived analysis
   normalized_cache: CopyCache<Place<'tcx>, Place<'tcx>>,
@@ -44,9 +58,15 @@ pub struct PlaceInfo<'a, 'tcx> {
 }
 
 impl<'a, 'tcx> PlaceInfo<'a, 'tcx> {
-  fn build_location_arg_domain(body: &Body) -> Rc<LocationOrArgDomain> {
+  fn build_location_arg_domain(
+    body: &Body,
+    globals: &Globals,
+  ) -> Rc<LocationOrArgDomain> {
     let all_locations = body.all_locations().map(LocationOrArg::Location);
-    let all_locals = body.args_iter().map(LocationOrArg::Arg);
+    let all_locals = body
+      .args_iter()
+      .chain(globals.representatives().map(|(_, local)| local))
+      .map(LocationOrArg::Arg);
     let domain = all_locations.chain(all_locals).collect::<Vec<_>>();
     Rc::new(LocationOrArgDomain::from_iter(domain))
   }
@@ -59,11 +79,17 @@ impl<'a, 'tcx> PlaceInfo<'a, 'tcx> {
   ) -> Self {
     block_timer!("aliases");
     let body = &body_with_facts.body;
-    let location_domain = Self::build_location_arg_domain(body);
-    let aliases = Aliases::build(tcx, def_id, body_with_facts);
+    let globals = Globals::build(tcx, body_with_facts);
+    let location_domain = Self::build_location_arg_domain(body, &globals);
+    let aliases = Aliases::build(tcx, def_id, body_with_facts, &globals);
+    let caveats = Self::pointer_limit_caveats(body, &aliases);
 
     PlaceInfo {
       aliases,
+      globals,
+      caveats,
+      body_with_facts,
+      flow_aliases: OnceCell::new(),
       tcx,
       body,
       def_id,
@@ -75,6 +101,30 @@ impl<'a, 'tcx> PlaceInfo<'a, 'tcx> {
     }
   }
 
+  // Reports the arguments whose pointers Aliases (and all_args) only partially track.
+  fn pointer_limit_caveats(
+    body: &Body<'tcx>,
+    aliases: &Aliases<'a, 'tcx>,
+  ) -> Vec<Caveat> {
+    aliases
+      .pointer_limited_args()
+      .iter()
+      .map(|arg| {
+        Caveat::new(
+          CaveatKind::PointerLimit,
+          LocationOrArg::Arg(*arg),
+          body,
+          "this argument contains deeply nested pointers, whose aliases are not tracked",
+        )
+      })
+      .collect()
+  }
+
+  /// Returns the caveats about soundness found while building the place metadata.
+  pub fn caveats(&self) -> &[Caveat] {
+    &self.caveats
+  }
+
   /// Normalizes a place via [`PlaceExt::normalize`] (cached).
   ///
   /// See the `PlaceExt` documentation for details on how normalization works.
@@ -88,12 +138,39 @@ impl<'a, 'tcx> PlaceInfo<'a, 'tcx> {
   ///
   /// For example, if `x = &y`, then `*x` aliases `y`.
   /// Note that an alias is NOT guaranteed to be of the same type as `place`!
+  ///
+  /// Places within a global also alias the same place through every other pointer
+  /// to that global, see [`Globals`].
   pub fn aliases(&self, place: Place<'tcx>) -> &PlaceSet<'tcx> {
     // note: important that aliases are computed on the unnormalized place
     // which contains region information
-    self
-      .aliases_cache
-      .get(&self.normalize(place), move |_| self.aliases.aliases(place))
+    self.aliases_cache.get(&self.normalize(place), move |_| {
+      let mut aliases = self.aliases.aliases(place);
+      let siblings = aliases
+        .iter()
+        .flat_map(|alias| self.globals.siblings(*alias))
+        .collect::<Vec<_>>();
+      aliases.extend(siblings);
+      aliases
+    })
+  }
+
+  /// Computes the aliases of a place before a given location.
+  ///
+  /// This is the subset of [`PlaceInfo::aliases`] which excludes loans that the place's
+  /// reference cannot hold at `location`. For example, if `r = &mut a` is later reassigned
+  /// to `r = &mut b`, then afterwards `*r` aliases `b` but not `a`.
+  /// See [`FlowSensitiveAliases`] for details.
+  pub fn aliases_at(&self, place: Place<'tcx>, location: Location) -> PlaceSet<'tcx> {
+    let aliases = self.aliases(place);
+    if is_extension_active(|mode| mode.pointer_mode == PointerMode::Conservative) {
+      return aliases.clone();
+    }
+
+    let flow_aliases = self
+      .flow_aliases
+      .get_or_init(|| FlowSensitiveAliases::build(self.tcx, self.body_with_facts));
+    flow_aliases.restrict(self.body, place, aliases, location)
   }
 
   /// Returns all reachable fields of `place` without going through references.
@@ -174,12 +251,41 @@ impl<'a, 'tcx> PlaceInfo<'a, 'tcx> {
     collector.loans
   }
 
+  /// Returns the globals referenced by the current body.
+  pub fn globals(&self) -> &Globals<'tcx> {
+    &self.globals
+  }
+
+  /// Returns the locals whose values at function entry are inputs to the current body,
+  /// i.e. the arguments followed by the representative pointer of each referenced global.
+  ///
+  /// These are exactly the locals `l` such that `LocationOrArg::Arg(l)` appears in the
+  /// results. Note that a global's representative is a temporary, not an argument, so
+  /// `Arg(l)` only denotes a parameter if `l` is in `body.args_iter()`. Consumers that map
+  /// `Arg` back to parameters must check this, and can use [`Globals::representatives`]
+  /// to name the other inputs.
+  pub fn input_locals(&self) -> impl Iterator<Item = Local> + '_ {
+    self
+      .body
+      .args_iter()
+      .chain(self.globals.representatives().map(|(_, local)| local))
+  }
+
   /// Returns all [direct](PlaceExt::is_direct) places reachable from arguments
-  /// to the current body.
+  /// to the current body, along with the places within each referenced global.
   pub fn all_args(
     &'a self,
   ) -> impl Iterator<Item = (Place<'tcx>, LocationOrArgIndex)> + 'a {
-    self.body.args_iter().flat_map(|local| {
+    let globals = self.globals.representatives().flat_map(|(_, local)| {
+      let location = local.to_index(&self.location_domain);
+      self
+        .tcx
+        .mk_place_deref(Place::from_local(local, self.tcx))
+        .interior_places(self.tcx, self.body, self.def_id)
+        .into_iter()
+        .map(move |place| (place, location))
+    });
+    let args = self.body.args_iter().flat_map(|local| {
       let location = local.to_index(&self.location_domain);
       let place = Place::from_local(local, self.tcx);
       let ptrs = place
@@ -188,14 +294,15 @@ impl<'a, 'tcx> PlaceInfo<'a, 'tcx> {
         .flat_map(|ptrs| {
           ptrs
             .into_iter()
-            .filter(|(ptr, _)| ptr.projection.len() <= 2)
+            .filter(|(ptr, _)| is_tracked_arg_pointer(*ptr))
             .map(|(ptr, _)| self.tcx.mk_place_deref(ptr))
         });
       ptrs
         .chain([place])
         .flat_map(|place| place.interior_places(self.tcx, self.body, self.def_id))
         .map(move |place| (place, location))
-    })
+    });
+    args.chain(globals)
   }
 
   /// Returns the [`LocationOrArgDomain`] for the current body.
@@ -355,4 +462,49 @@ fn main() {
       )
     });
   }
+
+  #[test]
+  fn test_placeinfo_aliases_at() {
+    let input = r#"
+fn main() {
+  let mut a = 0;
+  let mut b = 1;
+  let mut r = &mut a;
+  *r = 2;
+  r = &mut b;
+  *r = 3;
+}
+    "#;
+    placeinfo_harness(input, |tcx, body, place_info| {
+      let p = Placer::new(tcx, body);
+      let deref_r = p.local("r").deref().mk();
+      let writes = body
+        .all_locations()
+        .filter(|location| {
+          matches!(
+            body.stmt_at(*location).left().map(|stmt| &stmt.kind),
+            Some(StatementKind::Assign(box (place, _))) if *place == deref_r
+          )
+        })
+        .collect::<Vec<_>>();
+      assert_eq!(writes.len(), 2);
+
+      // flow-insensitively, r could point to either a or b
+      compare_sets(place_info.aliases(deref_r), &hashset! {
+        deref_r,
+        p.local("a").mk(),
+        p.local("b").mk(),
+      });
+
+      // but r is reassigned between the two writes
+      compare_sets(place_info.aliases_at(deref_r, writes[0]), hashset! {
+        deref_r,
+        p.local("a").mk(),
+      });
+      compare_sets(place_info.aliases_at(deref_r, writes[1]), hashset! {
+        deref_r,
+        p.local("b").mk(),
+      });
+    });
+  }
 }
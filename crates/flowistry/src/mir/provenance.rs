@@ -0,0 +1,485 @@
+//! Flow-sensitive refinement of the alias analysis.
+//!
+//! [`Aliases`](super::aliases::Aliases) computes the loans a reference could point to anywhere
+//! in a body, based on the outlives-constraints of its lifetime. So if a reference is reassigned,
+//! e.g. `r = &mut a; ..; r = &mut b;`, then `*r` aliases both `a` and `b` at every location.
+//!
+//! [`FlowSensitiveAliases`] refines these aliases at a given location with [`ProvenanceAnalysis`],
+//! a dataflow analysis that computes which loans in the [`BorrowSet`] of a [`BodyWithBorrowckFacts`]
+//! are reachable from the value of each local. Assigning a new value to a local kills the loans it held
+//! before. Values that Flowistry cannot track, such as raw pointers or arguments, could hold any loan.
+//! An alias that comes from a loan is only kept if the dereferenced reference could hold that loan.
+//!
+//! Note that we do not need to separately check whether a loan is live: if a reference holding a loan
+//! is used at a location, then borrowck ensures the loan's region is live at that location.
+//!
+//! Why not use borrowck's facts directly? The Polonius input facts in [`BodyWithBorrowckFacts`]
+//! say where a loan is killed (its borrowed place is overwritten) and where variables are live,
+//! but not which loans a reference holds at a location. That relation (`origin_contains_loan_at`)
+//! is only computed by Polonius itself, which is too slow to run on every body. And NLL region
+//! inference is flow-insensitive in exactly the way we want to avoid: the region of `r` contains
+//! the loans of both `&mut a` and `&mut b` at every point where `r` is live. So we compute the
+//! loans held by each local with a small dataflow analysis over the loans of the [`BorrowSet`].
+
+use std::cell::RefCell;
+
+use log::warn;
+use rustc_borrowck::consumers::{BodyWithBorrowckFacts, BorrowIndex, BorrowSet};
+use rustc_data_structures::fx::FxHashMap as HashMap;
+use rustc_index::{IndexVec, bit_set::DenseBitSet};
+use rustc_middle::{
+  mir::{visit::Visitor, *},
+  ty::TyCtxt,
+};
+use rustc_mir_dataflow::{Analysis, JoinSemiLattice, Results, fmt::DebugWithContext};
+use rustc_span::source_map::Spanned;
+use rustc_utils::{PlaceExt, cache::Cache, mir::place::PlaceCollector};
+
+use super::utils::PlaceSet;
+
+/// The loans that could be held by each local at a given location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoanProvenance {
+  loans: IndexVec<Local, DenseBitSet<BorrowIndex>>,
+  unknown: DenseBitSet<Local>,
+}
+
+impl JoinSemiLattice for LoanProvenance {
+  fn join(&mut self, other: &Self) -> bool {
+    let mut changed = self.unknown.join(&other.unknown);
+    for (loans, other_loans) in self.loans.iter_mut().zip(other.loans.iter()) {
+      changed |= loans.join(other_loans);
+    }
+    changed
+  }
+}
+
+impl<C> DebugWithContext<C> for LoanProvenance {}
+
+/// The loans contained in a value, or `unknown` if the value could contain any loan.
+struct LoanValue {
+  loans: DenseBitSet<BorrowIndex>,
+  unknown: bool,
+}
+
+impl LoanProvenance {
+  fn add_local(&self, value: &mut LoanValue, local: Local) {
+    value.loans.union(&self.loans[local]);
+    value.unknown |= self.unknown.contains(local);
+  }
+
+  fn set_local(&mut self, local: Local, value: &LoanValue) {
+    self.loans[local].clone_from(&value.loans);
+    if value.unknown {
+      self.unknown.insert(local);
+    } else {
+      self.unknown.remove(local);
+    }
+  }
+
+  fn union_local(&mut self, local: Local, value: &LoanValue) {
+    self.loans[local].union(&value.loans);
+    if value.unknown {
+      self.unknown.insert(local);
+    }
+  }
+}
+
+/// Dataflow analysis that computes the [`LoanProvenance`] before each location.
+pub struct ProvenanceAnalysis<'tcx> {
+  tcx: TyCtxt<'tcx>,
+  local_decls: IndexVec<Local, LocalDecl<'tcx>>,
+  num_loans: usize,
+  loan_locations: HashMap<Location, BorrowIndex>,
+
+  /// Locals whose address is taken, and so may be written through a pointer.
+  borrowed_locals: DenseBitSet<Local>,
+
+  /// The value of the arguments to the call being analyzed, which flows into
+  /// the destination in [`Analysis::apply_call_return_effect`].
+  call_value: Option<LoanValue>,
+}
+
+impl<'tcx> ProvenanceAnalysis<'tcx> {
+  fn new(tcx: TyCtxt<'tcx>, body: &Body<'tcx>, borrow_set: &BorrowSet<'tcx>) -> Self {
+    struct CollectBorrowed(DenseBitSet<Local>);
+    impl<'tcx> Visitor<'tcx> for CollectBorrowed {
+      fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, _location: Location) {
+        if let Rvalue::Ref(_, _, place) | Rvalue::RawPtr(_, place) = rvalue {
+          self.0.insert(place.local);
+        }
+      }
+    }
+
+    let mut collector = CollectBorrowed(DenseBitSet::new_empty(body.local_decls.len()));
+    collector.visit_body(body);
+
+    ProvenanceAnalysis {
+      tcx,
+      local_decls: body.local_decls.clone(),
+      num_loans: borrow_set.location_map().len(),
+      loan_locations: loan_locations(body, borrow_set),
+      borrowed_locals: collector.0,
+      call_value: None,
+    }
+  }
+
+  fn empty_value(&self) -> LoanValue {
+    LoanValue {
+      loans: DenseBitSet::new_empty(self.num_loans),
+      unknown: false,
+    }
+  }
+
+  /// Adds the loans of a value read out of `place`. Values read through a
+  /// pointer could contain any loan.
+  fn read_place(
+    &self,
+    state: &LoanProvenance,
+    place: Place<'tcx>,
+    value: &mut LoanValue,
+  ) {
+    let ty = place.ty(&self.local_decls, self.tcx).ty;
+    if ty.is_primitive() || ty.is_unit() {
+      return;
+    }
+    value.unknown |= place.is_indirect();
+    state.add_local(value, place.local);
+  }
+
+  fn rvalue_value(
+    &self,
+    state: &LoanProvenance,
+    rvalue: &Rvalue<'tcx>,
+    location: Location,
+  ) -> LoanValue {
+    let mut value = self.empty_value();
+    match rvalue {
+      // A reference can reach its own loan, and any loans reachable from the borrowed place.
+      // For example, `&mut (*r).0` points into whatever `r` points to.
+      Rvalue::Ref(_, _, place) => {
+        if let Some(loan) = self.loan_locations.get(&location) {
+          value.loans.insert(*loan);
+        }
+        state.add_local(&mut value, place.local);
+      }
+
+      // Raw pointers are not tracked by borrowck.
+      Rvalue::RawPtr(..) | Rvalue::Cast(CastKind::PointerWithExposedProvenance, ..) => {
+        value.unknown = true;
+      }
+
+      _ => {
+        let mut collector = PlaceCollector::default();
+        collector.visit_rvalue(rvalue, location);
+        for place in collector.0 {
+          self.read_place(state, place, &mut value);
+        }
+      }
+    }
+    value
+  }
+
+  fn args_value(
+    &self,
+    state: &LoanProvenance,
+    args: &[Spanned<Operand<'tcx>>],
+  ) -> LoanValue {
+    let mut value = self.empty_value();
+    for arg in args {
+      if let Some(place) = arg.node.place() {
+        self.read_place(state, place, &mut value);
+      }
+    }
+    value
+  }
+
+  /// Adds the loans of a value written through an unknown pointer. Any local with its address
+  /// taken could now hold the loans, as well as any local which reaches them through a loan.
+  fn write_unknown(&self, state: &mut LoanProvenance, value: &LoanValue) {
+    for local in state.loans.indices() {
+      if self.borrowed_locals.contains(local) || !state.loans[local].is_empty() {
+        state.union_local(local, value);
+      }
+    }
+  }
+
+  fn write_place(
+    &self,
+    state: &mut LoanProvenance,
+    place: Place<'tcx>,
+    value: &LoanValue,
+  ) {
+    if place.is_indirect() {
+      self.write_unknown(state, value);
+    } else if place.projection.is_empty() {
+      state.set_local(place.local, value);
+    } else {
+      state.union_local(place.local, value);
+    }
+  }
+}
+
+impl<'tcx> Analysis<'tcx> for ProvenanceAnalysis<'tcx> {
+  type Domain = LoanProvenance;
+
+  const NAME: &'static str = "ProvenanceAnalysis";
+
+  fn bottom_value(&self, body: &Body<'tcx>) -> Self::Domain {
+    LoanProvenance {
+      loans: IndexVec::from_elem_n(
+        DenseBitSet::new_empty(self.num_loans),
+        body.local_decls.len(),
+      ),
+      unknown: DenseBitSet::new_empty(body.local_decls.len()),
+    }
+  }
+
+  fn initialize_start_block(&self, body: &Body<'tcx>, state: &mut Self::Domain) {
+    // Arguments hold loans from the caller, which could be anything.
+    for arg in body.args_iter() {
+      state.unknown.insert(arg);
+    }
+  }
+
+  fn apply_primary_statement_effect(
+    &mut self,
+    state: &mut Self::Domain,
+    statement: &Statement<'tcx>,
+    location: Location,
+  ) {
+    if let StatementKind::Assign(box (place, rvalue)) = &statement.kind {
+      let value = self.rvalue_value(state, rvalue, location);
+      self.write_place(state, *place, &value);
+    }
+  }
+
+  fn apply_primary_terminator_effect<'mir>(
+    &mut self,
+    state: &mut Self::Domain,
+    terminator: &'mir Terminator<'tcx>,
+    _location: Location,
+  ) -> TerminatorEdges<'mir, 'tcx> {
+    match &terminator.kind {
+      // The callee could write its arguments into any pointer it is given.
+      TerminatorKind::Call { args, .. } | TerminatorKind::TailCall { args, .. } => {
+        let value = self.args_value(state, args);
+        self.write_unknown(state, &value);
+        self.call_value = Some(value);
+      }
+      TerminatorKind::InlineAsm { .. } => {
+        state.unknown.insert_all();
+      }
+      _ => {}
+    }
+    terminator.edges()
+  }
+
+  fn apply_call_return_effect(
+    &mut self,
+    state: &mut Self::Domain,
+    _block: BasicBlock,
+    return_places: CallReturnPlaces<'_, 'tcx>,
+  ) {
+    match (return_places, &self.call_value) {
+      (CallReturnPlaces::Call(destination), Some(value)) => {
+        self.write_place(state, destination, value);
+      }
+      _ => return_places.for_each(|place| {
+        state.unknown.insert(place.local);
+      }),
+    }
+  }
+}
+
+/// Computes the aliases of a place at a given location.
+///
+/// See the [module-level documentation](self) for an explanation.
+pub struct FlowSensitiveAliases<'tcx> {
+  tcx: TyCtxt<'tcx>,
+  borrowed_places: IndexVec<BorrowIndex, Place<'tcx>>,
+
+  /// False if some loans could not be matched to their borrow, in which case no aliases are
+  /// removed.
+  complete: bool,
+
+  analysis: RefCell<ProvenanceAnalysis<'tcx>>,
+  results: Results<LoanProvenance>,
+
+  /// The state before the last queried location. The fixpoint mostly queries the locations of
+  /// a block in order, so this state can be moved forward instead of starting from the block's
+  /// entry state each time.
+  cursor: RefCell<(Location, LoanProvenance)>,
+  loans_cache: Cache<(Local, Location), Option<DenseBitSet<BorrowIndex>>>,
+}
+
+impl<'tcx> FlowSensitiveAliases<'tcx> {
+  /// Runs the provenance analysis on a given `body_with_facts`.
+  pub fn build(tcx: TyCtxt<'tcx>, body_with_facts: &BodyWithBorrowckFacts<'tcx>) -> Self {
+    let body = &body_with_facts.body;
+    let borrow_set = &body_with_facts.borrow_set;
+    let analysis = ProvenanceAnalysis::new(tcx, body, borrow_set);
+    let complete = analysis.loan_locations.len() == analysis.num_loans;
+    let provenance = analysis.iterate_to_fixpoint(tcx, body, None);
+    let cursor = (Location::START, provenance.results[START_BLOCK].clone());
+
+    FlowSensitiveAliases {
+      tcx,
+      borrowed_places: borrow_set
+        .location_map()
+        .values()
+        .map(|borrow| borrow.borrowed_place())
+        .collect(),
+      complete,
+      analysis: RefCell::new(provenance.analysis),
+      results: provenance.results,
+      cursor: RefCell::new(cursor),
+      loans_cache: Cache::default(),
+    }
+  }
+
+  /// Returns the loans that the pointer `ptr` could hold before `location`,
+  /// or `None` if it could hold any loan.
+  pub fn loans_at(
+    &self,
+    body: &Body<'tcx>,
+    ptr: Place<'tcx>,
+    location: Location,
+  ) -> Option<DenseBitSet<BorrowIndex>> {
+    if ptr.is_indirect() || !self.complete {
+      return None;
+    }
+
+    self
+      .loans_cache
+      .get(&(ptr.local, location), |(local, location)| {
+        let mut cursor = self.cursor.borrow_mut();
+        let (position, state) = &mut *cursor;
+        if position.block != location.block
+          || position.statement_index > location.statement_index
+        {
+          *position = location.block.start_location();
+          state.clone_from(&self.results[location.block]);
+        }
+
+        // The analysis has no early effects and only terminators follow the last statement,
+        // so applying the statements before `location` gives the state before its primary effect.
+        let mut analysis = self.analysis.borrow_mut();
+        let statements = &body.basic_blocks[location.block].statements;
+        while position.statement_index < location.statement_index {
+          let statement = &statements[position.statement_index];
+          analysis.apply_primary_statement_effect(state, statement, *position);
+          position.statement_index += 1;
+        }
+
+        (!state.unknown.contains(local)).then(|| state.loans[local].clone())
+      })
+      .clone()
+  }
+
+  /// Removes the elements of `aliases`, the flow-insensitive aliases of `place`,
+  /// that come from loans which `place` cannot reach at `location`.
+  pub fn restrict(
+    &self,
+    body: &Body<'tcx>,
+    place: Place<'tcx>,
+    aliases: &PlaceSet<'tcx>,
+    location: Location,
+  ) -> PlaceSet<'tcx> {
+    let Some(ptr) = deref_base(place, self.tcx)
+      .filter(|ptr| ptr.ty(body.local_decls(), self.tcx).ty.is_ref())
+    else {
+      return aliases.clone();
+    };
+    let Some(loans) = self.loans_at(body, ptr, location) else {
+      return aliases.clone();
+    };
+
+    aliases
+      .iter()
+      .filter(|alias| {
+        let from_loan = self
+          .borrowed_places
+          .iter()
+          .any(|borrowed| overlaps(*borrowed, **alias));
+        !from_loan
+          || loans
+            .iter()
+            .any(|loan| overlaps(self.borrowed_places[loan], **alias))
+      })
+      .copied()
+      .collect()
+  }
+}
+
+/// Maps the location of each borrow statement in `body` to its loan in `borrow_set`.
+///
+/// The borrow set is computed before
+/// [`simplify_mir`](rustc_utils::mir::borrowck_facts::simplify_mir) removes storage statements, so its
+/// locations can be shifted within a block. Because the removed statements are never borrows,
+/// the borrows of a block still occur in the same order. A loan that cannot be matched is logged,
+/// and then [`FlowSensitiveAliases`] does not remove any aliases.
+fn loan_locations<'tcx>(
+  body: &Body<'tcx>,
+  borrow_set: &BorrowSet<'tcx>,
+) -> HashMap<Location, BorrowIndex> {
+  let mut loans = borrow_set
+    .location_map()
+    .iter()
+    .enumerate()
+    .map(|(i, (location, borrow))| (*location, BorrowIndex::from_usize(i), borrow))
+    .collect::<Vec<_>>();
+  loans.sort_by_key(|(location, ..)| *location);
+
+  let mut loans = loans.into_iter().peekable();
+  let mut loan_locations = HashMap::default();
+  for (block, data) in body.basic_blocks.iter_enumerated() {
+    for (statement_index, statement) in data.statements.iter().enumerate() {
+      let StatementKind::Assign(box (assigned, Rvalue::Ref(_, _, borrowed))) =
+        &statement.kind
+      else {
+        continue;
+      };
+      while let Some((location, ..)) =
+        loans.next_if(|(location, ..)| location.block < block)
+      {
+        warn!("Could not find the borrow at {location:?} after simplifying the MIR");
+      }
+      if let Some((_, loan, _)) = loans.next_if(|(location, _, borrow)| {
+        location.block == block
+          && borrow.assigned_place() == *assigned
+          && borrow.borrowed_place() == *borrowed
+      }) {
+        loan_locations.insert(
+          Location {
+            block,
+            statement_index,
+          },
+          loan,
+        );
+      }
+    }
+  }
+  for (location, ..) in loans {
+    warn!("Could not find the borrow at {location:?} after simplifying the MIR");
+  }
+  loan_locations
+}
+
+/// Returns the pointer dereferenced by the last deref in `place`, if any.
+fn deref_base<'tcx>(place: Place<'tcx>, tcx: TyCtxt<'tcx>) -> Option<Place<'tcx>> {
+  let (ptr, _) = place
+    .iter_projections()
+    .rfind(|(_, elem)| *elem == ProjectionElem::Deref)?;
+  Some(Place::from_ref(ptr, tcx))
+}
+
+/// Returns true if one place is a prefix of the other.
+fn overlaps<'tcx>(p1: Place<'tcx>, p2: Place<'tcx>) -> bool {
+  p1.local == p2.local
+    && p1
+      .projection
+      .iter()
+      .zip(p2.projection.iter())
+      .all(|(e1, e2)| e1 == e2)
+}
@@ -23,10 +23,50 @@ use rustc_utils::{
 };
 
 use crate::{
-  extensions::{ContextMode, EVAL_MODE, EvalMode, MutabilityMode, PointerMode},
-  infoflow,
+  extensions::{
+    AliasMode, ContextMode, ControlMode, EVAL_MODE, EvalMode, MutabilityMode, PointerMode,
+  },
+  infoflow::{
+    self,
+    summaries::{FLOW_SUMMARIES, FlowSummaries},
+  },
 };
 
+mod noninterference;
+pub use noninterference::test_noninterference;
+
+/// Parses the evaluation mode and flow summaries from the first line of a test program.
+fn parse_header(header: &str) -> (EvalMode, FlowSummaries) {
+  let mut mode = EvalMode::default();
+  if header.starts_with("/*") {
+    if header.contains("recurse") {
+      mode.context_mode = ContextMode::Recurse;
+    }
+    if header.contains("ignoremut") {
+      mode.mutability_mode = MutabilityMode::IgnoreMut;
+    }
+    if header.contains("conservative") {
+      mode.pointer_mode = PointerMode::Conservative;
+    }
+    if header.contains("flowsensitive") {
+      mode.alias_mode = AliasMode::FlowSensitive;
+    }
+    if header.contains("ignorecontrol") {
+      mode.control_mode = ControlMode::Ignore;
+    }
+  }
+
+  // A header of the form `/* summaries {...} */` provides flow summaries as JSON.
+  let summaries = match header.split_once("summaries") {
+    Some((_, json)) => {
+      FlowSummaries::parse(json.trim_end().trim_end_matches("*/")).unwrap()
+    }
+    None => FlowSummaries::default(),
+  };
+
+  (mode, summaries)
+}
+
 pub fn compile_body_with_range(
   input: impl Into<String>,
   compute_target: impl FnOnce() -> ByteRange + Send,
@@ -125,21 +165,9 @@ pub fn test_command_output(
         input_ranges["`("][0]
       },
       |tcx, body_id, body_with_facts, target: ByteRange| {
-        let header = input.lines().next().unwrap();
-        let mut mode = EvalMode::default();
-        if header.starts_with("/*") {
-          if header.contains("recurse") {
-            mode.context_mode = ContextMode::Recurse;
-          }
-          if header.contains("ignoremut") {
-            mode.mutability_mode = MutabilityMode::IgnoreMut;
-          }
-          if header.contains("conservative") {
-            mode.pointer_mode = PointerMode::Conservative;
-          }
-        }
-
+        let (mode, summaries) = parse_header(input.lines().next().unwrap());
         fluid_set!(EVAL_MODE, &mode);
+        fluid_set!(FLOW_SUMMARIES, summaries);
 
         let target = target.to_span(tcx).unwrap();
         let results = infoflow::compute_flow(tcx, body_id, body_with_facts);
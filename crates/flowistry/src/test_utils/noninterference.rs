@@ -0,0 +1,591 @@
+//! Checking Flowistry's slices against executions of the sliced program.
+//!
+//! If Flowistry is sound, then a parameter outside the backward slice of an output cannot affect
+//! that output. For every `pub fn` of a test program, [`test_noninterference`] slices each output
+//! of the function, i.e. its return value and the data behind each `&mut` parameter. It then
+//! compiles the program with a generated `main` that calls the function in groups, where the calls
+//! of a group share random values for the parameters in the slice and use different random values
+//! for the others. If an output differs within a group, the test fails with the inputs of both
+//! calls.
+//!
+//! Parameters must be built from integers, `bool`, tuples, arrays, slices and shared references,
+//! or be `&mut` references to sized data of these types, and the return value must be one of
+//! these or `()`. Calls that panic (e.g. due to division by
+//! zero) are ignored. The random values are the same on every run, set `SEED` to use others.
+//! Like other test programs, a program can set the evaluation mode in its first line.
+//!
+//! Functions whose name starts with `known_unsound_` record known unsoundness, so the test fails
+//! if *no* counterexample is found for them.
+
+use std::{env, fmt, fs, path::Path, process::Command};
+
+use anyhow::{Context, Result, bail, ensure};
+use fluid_let::fluid_set;
+use log::{debug, info};
+use rustc_data_structures::fx::FxHashSet as HashSet;
+use rustc_hir::def::DefKind;
+use rustc_middle::{
+  mir::{Local, Place, ProjectionElem},
+  ty::{Ty, TyCtxt, TyKind, TypingEnv},
+};
+use rustc_session::config::Sysroot;
+use rustc_utils::{
+  BodyExt, PlaceExt,
+  mir::{
+    borrowck_facts::{self, get_body_with_borrowck_facts},
+    location_or_arg::LocationOrArg,
+  },
+  test_utils::CompileBuilder,
+};
+
+use super::parse_header;
+use crate::{
+  extensions::EVAL_MODE,
+  infoflow::{self, Direction, summaries::FLOW_SUMMARIES},
+};
+
+/// The number of groups of calls per output.
+const GROUPS: usize = 16;
+
+/// The number of calls per group.
+const GROUP_SIZE: usize = 5;
+
+/// The type of a value that can be generated at random.
+#[derive(Debug, Clone)]
+enum ValueType {
+  Bool,
+  Int {
+    name: &'static str,
+    bits: u64,
+    signed: bool,
+  },
+  Tuple(Vec<ValueType>),
+  Array(Box<ValueType>, u64),
+  Slice(Box<ValueType>),
+  Ref(Box<ValueType>),
+}
+
+impl ValueType {
+  fn from_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Self> {
+    let value_type = |ty| ValueType::from_ty(tcx, ty).map(Box::new);
+    Some(match ty.kind() {
+      TyKind::Bool => ValueType::Bool,
+      TyKind::Int(int) => ValueType::Int {
+        name: int.name_str(),
+        bits: int.bit_width().unwrap_or(64),
+        signed: true,
+      },
+      TyKind::Uint(uint) => ValueType::Int {
+        name: uint.name_str(),
+        bits: uint.bit_width().unwrap_or(64),
+        signed: false,
+      },
+      TyKind::Tuple(tys) => ValueType::Tuple(
+        tys
+          .iter()
+          .map(|ty| ValueType::from_ty(tcx, ty))
+          .collect::<Option<_>>()?,
+      ),
+      TyKind::Array(ty, len) => {
+        ValueType::Array(value_type(*ty)?, len.try_to_target_usize(tcx)?)
+      }
+      TyKind::Slice(ty) => ValueType::Slice(value_type(*ty)?),
+      TyKind::Ref(_, ty, mutability) if !mutability.is_mut() => {
+        ValueType::Ref(value_type(*ty)?)
+      }
+      _ => return None,
+    })
+  }
+
+  /// Generates a Rust expression for a random value of this type.
+  fn random_value(&self, rng: &mut Rng) -> String {
+    let values = |ty: &ValueType, len: u64, rng: &mut Rng| {
+      (0 .. len)
+        .map(|_| ty.random_value(rng))
+        .collect::<Vec<_>>()
+        .join(", ")
+    };
+    match self {
+      ValueType::Bool => rng.next_bool().to_string(),
+      ValueType::Int { name, bits, signed } => {
+        // Small values take branches that compare against constants, and large values take the
+        // rest of the range.
+        let bits = (*bits).min(64);
+        let small = rng.next_bool();
+        if *signed {
+          let value = if small {
+            (rng.next() % 9) as i64 - 4
+          } else {
+            (rng.next() as i64) >> (64 - bits)
+          };
+          format!("{value}{name}")
+        } else {
+          let value = if small {
+            rng.next() % 8
+          } else {
+            rng.next() >> (64 - bits)
+          };
+          format!("{value}{name}")
+        }
+      }
+      ValueType::Tuple(tys) => match &tys[..] {
+        [ty] => format!("({},)", ty.random_value(rng)),
+        _ => format!(
+          "({})",
+          tys
+            .iter()
+            .map(|ty| ty.random_value(rng))
+            .collect::<Vec<_>>()
+            .join(", ")
+        ),
+      },
+      ValueType::Array(ty, len) => format!("[{}]", values(ty, *len, rng)),
+      ValueType::Slice(ty) => {
+        let len = rng.next() % 4;
+        format!("[{}]", values(ty, len, rng))
+      }
+      ValueType::Ref(ty) => format!("&{}", ty.random_value(rng)),
+    }
+  }
+}
+
+impl fmt::Display for ValueType {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ValueType::Bool => write!(f, "bool"),
+      ValueType::Int { name, .. } => write!(f, "{name}"),
+      ValueType::Tuple(tys) => {
+        write!(f, "(")?;
+        for ty in tys {
+          write!(f, "{ty},")?;
+        }
+        write!(f, ")")
+      }
+      ValueType::Array(ty, len) => write!(f, "[{ty}; {len}]"),
+      ValueType::Slice(ty) => write!(f, "[{ty}]"),
+      ValueType::Ref(ty) => write!(f, "&{ty}"),
+    }
+  }
+}
+
+/// The splitmix64 generator, so that the random values only depend on the seed.
+struct Rng(u64);
+
+impl Rng {
+  fn next(&mut self) -> u64 {
+    self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = self.0;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+  }
+
+  fn next_bool(&mut self) -> bool {
+    self.next() >> 63 == 1
+  }
+}
+
+struct Param {
+  name: String,
+  ty: ValueType,
+
+  /// Whether the parameter is a `&mut` to a value of type `ty`, which is then an output.
+  mutable: bool,
+}
+
+struct Output {
+  /// The parameter behind which the output is, or `None` for the return value.
+  param: Option<usize>,
+
+  /// The parameters that are not in the output's backward slice.
+  independent: Vec<usize>,
+}
+
+/// A function of the test program whose outputs are checked.
+struct Function {
+  path: String,
+  params: Vec<Param>,
+  outputs: Vec<Output>,
+
+  /// Whether a counterexample is expected, because the function's name starts with
+  /// `known_unsound_`.
+  known_unsound: bool,
+}
+
+impl Function {
+  fn output_name(&self, output: &Output) -> String {
+    match output.param {
+      Some(index) => format!("`*{}`", self.params[index].name),
+      None => "the return value".to_string(),
+    }
+  }
+
+  fn fmt_call(&self, args: &[String]) -> String {
+    let args = self
+      .params
+      .iter()
+      .zip(args)
+      .map(|(param, arg)| {
+        if param.mutable {
+          format!("&mut {arg}")
+        } else {
+          arg.clone()
+        }
+      })
+      .collect::<Vec<_>>();
+    format!("{}({})", self.path, args.join(", "))
+  }
+
+  /// Generates a wrapper that calls the function and returns the `Debug` output of each of its
+  /// outputs, taking `&mut` parameters by value.
+  fn wrapper(&self, name: &str) -> String {
+    let params = self
+      .params
+      .iter()
+      .enumerate()
+      .map(|(index, param)| {
+        let binding = if param.mutable { "mut " } else { "" };
+        format!("{binding}a{index}: {}", param.ty)
+      })
+      .collect::<Vec<_>>();
+    let args = self
+      .params
+      .iter()
+      .enumerate()
+      .map(|(index, param)| {
+        if param.mutable {
+          format!("&mut a{index}")
+        } else {
+          format!("a{index}")
+        }
+      })
+      .collect::<Vec<_>>();
+    let outputs = self
+      .outputs
+      .iter()
+      .map(|output| match output.param {
+        Some(index) => format!("format!(\"{{:?}}\", a{index})"),
+        None => "ret".to_string(),
+      })
+      .collect::<Vec<_>>();
+    format!(
+      "fn {name}({}) -> Vec<String> {{\n  let ret = format!(\"{{:?}}\", {}({}));\n  vec![{}]\n}}\n",
+      params.join(", "),
+      self.path,
+      args.join(", "),
+      outputs.join(", ")
+    )
+  }
+}
+
+/// Finds the checked functions of the program and slices their outputs.
+fn slice_functions(tcx: TyCtxt) -> Result<Vec<Function>> {
+  let mut functions = Vec::new();
+  for def_id in tcx.hir_body_owners() {
+    if tcx.def_kind(def_id) != DefKind::Fn || !tcx.visibility(def_id).is_public() {
+      continue;
+    }
+    let path = tcx.def_path_str(def_id);
+    ensure!(
+      !tcx.generics_of(def_id).requires_monomorphization(tcx),
+      "`{path}` is generic"
+    );
+
+    let body_id = tcx.hir_body_owned_by(def_id).id();
+    let sig = tcx.fn_sig(def_id).instantiate_identity().skip_binder();
+    let typing_env = TypingEnv::post_analysis(tcx, def_id);
+    let params = tcx
+      .hir_body(body_id)
+      .params
+      .iter()
+      .zip(sig.inputs())
+      .enumerate()
+      .map(|(index, (param, ty))| {
+        let name = match param.pat.simple_ident() {
+          Some(ident) => ident.to_string(),
+          None => format!("argument {index}"),
+        };
+        let (ty, mutable) = match ty.kind() {
+          TyKind::Ref(_, ty, mutability) if mutability.is_mut() => (*ty, true),
+          _ => (*ty, false),
+        };
+        // Values behind `&mut` are generated as locals, so they must be sized.
+        let ty = ValueType::from_ty(tcx, ty)
+          .filter(|_| !mutable || ty.is_sized(tcx, typing_env))
+          .with_context(|| format!("`{name}` of `{path}` has an unsupported type"))?;
+        Ok(Param { name, ty, mutable })
+      })
+      .collect::<Result<Vec<_>>>()?;
+
+    let returns = !sig.output().is_unit();
+    ensure!(
+      !returns || ValueType::from_ty(tcx, sig.output()).is_some(),
+      "`{path}` has an unsupported return type"
+    );
+
+    debug!("Slicing the outputs of {path}");
+    let body_with_facts = get_body_with_borrowck_facts(tcx, def_id);
+    let results = infoflow::compute_flow(tcx, body_id, body_with_facts);
+    let body = results.analysis.body;
+    let outputs = returns
+      .then_some(None)
+      .into_iter()
+      .chain(
+        params
+          .iter()
+          .enumerate()
+          .filter(|(_, param)| param.mutable)
+          .map(|(index, _)| Some(index)),
+      )
+      .map(|param| {
+        let place = match param {
+          Some(index) => Place::from_local(Local::from_usize(index + 1), tcx)
+            .project_deeper(&[ProjectionElem::Deref], tcx),
+          None => Place::return_place(),
+        };
+        let targets = body
+          .all_returns()
+          .map(|location| (place, LocationOrArg::Location(location)))
+          .collect::<Vec<_>>();
+        let deps =
+          infoflow::compute_dependencies(&results, vec![targets], Direction::Backward)
+            .remove(0);
+        // Only parameters are varied between runs, so `Arg` inputs for referenced
+        // globals are deliberately not checked.
+        let independent = (0 .. params.len())
+          .filter(|index| {
+            !deps.contains(LocationOrArg::Arg(Local::from_usize(index + 1)))
+          })
+          .collect();
+        Output { param, independent }
+      })
+      .collect::<Vec<_>>();
+
+    functions.push(Function {
+      known_unsound: tcx.item_name(def_id).as_str().starts_with("known_unsound_"),
+      path,
+      params,
+      outputs,
+    });
+  }
+  Ok(functions)
+}
+
+/// A group of calls for an output, where only the output's independent parameters vary.
+struct Group {
+  function: usize,
+  output: usize,
+  calls: Vec<Vec<String>>,
+}
+
+fn generate_groups(functions: &[Function], rng: &mut Rng) -> Vec<Group> {
+  let mut groups = Vec::new();
+  for (function_index, function) in functions.iter().enumerate() {
+    for (output_index, output) in function.outputs.iter().enumerate() {
+      if output.independent.is_empty() {
+        continue;
+      }
+      for _ in 0 .. GROUPS {
+        let base = function
+          .params
+          .iter()
+          .map(|param| param.ty.random_value(rng))
+          .collect::<Vec<_>>();
+        let mut calls = vec![base.clone()];
+        for _ in 1 .. GROUP_SIZE {
+          let mut call = base.clone();
+          for index in &output.independent {
+            call[*index] = function.params[*index].ty.random_value(rng);
+          }
+          calls.push(call);
+        }
+        groups.push(Group {
+          function: function_index,
+          output: output_index,
+          calls,
+        });
+      }
+    }
+  }
+  groups
+}
+
+/// Generates a `main` that runs every call of `groups`, and prints the outputs of each call on a
+/// line, separated by tabs.
+fn generate_main(functions: &[Function], groups: &[Group]) -> String {
+  let mut main = String::from(
+    "\nfn __flowistry_run(call: impl FnOnce() -> Vec<String>) -> String {
+  match std::panic::catch_unwind(std::panic::AssertUnwindSafe(call)) {
+    Ok(outputs) => outputs.join(\"\\t\"),
+    Err(_) => \"<panic>\".to_string(),
+  }
+}\n\n",
+  );
+  for (index, function) in functions.iter().enumerate() {
+    main.push_str(&function.wrapper(&format!("__flowistry_call_{index}")));
+    main.push('\n');
+  }
+
+  main.push_str("fn main() {\n  std::panic::set_hook(Box::new(|_| {}));\n");
+  for group in groups {
+    for call in &group.calls {
+      main.push_str(&format!(
+        "  println!(\"{{}}\", __flowistry_run(|| __flowistry_call_{}({})));\n",
+        group.function,
+        call.join(", ")
+      ));
+    }
+  }
+  main.push_str("}\n");
+  main
+}
+
+/// Compiles and runs `source`, and returns its standard output.
+fn run_program(name: &str, source: &str) -> Result<String> {
+  let dir = env::temp_dir().join("flowistry-noninterference");
+  fs::create_dir_all(&dir)?;
+  let file_name = format!("{name}_{}", std::process::id());
+  let source_path = dir.join(format!("{file_name}.rs"));
+  let binary_path = dir.join(&file_name);
+  fs::write(&source_path, source)?;
+
+  // Use the compiler of the toolchain under test rather than whichever `rustc` is on the PATH.
+  let rustc = Sysroot::new(None).path().join("bin").join("rustc");
+
+  let run = || -> Result<String> {
+    let output = Command::new(&rustc)
+      .args([
+        "--edition=2024",
+        "--crate-name",
+        name,
+        "--allow",
+        "warnings",
+      ])
+      .args(["-C", "overflow-checks=off", "-o"])
+      .arg(&binary_path)
+      .arg(&source_path)
+      .output()?;
+    ensure!(
+      output.status.success(),
+      "Failed to compile {name}:\n{}",
+      String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = Command::new(&binary_path).output()?;
+    ensure!(
+      output.status.success(),
+      "Failed to run {name}:\n{}",
+      String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8(output.stdout)?)
+  };
+  let stdout = run();
+
+  // The binary does not exist if the program failed to compile.
+  let _ = fs::remove_file(&source_path);
+  let _ = fs::remove_file(&binary_path);
+  stdout
+}
+
+/// Checks that the outputs of each `pub fn` in a test program do not depend on the parameters
+/// outside of their backward slices.
+pub fn test_noninterference(path: &Path) {
+  let inner = || -> Result<()> {
+    info!("Testing {}", path.file_name().unwrap().to_string_lossy());
+    let input = fs::read_to_string(path)?;
+    let name = path.file_stem().unwrap().to_string_lossy().to_string();
+
+    let mut functions = None;
+    borrowck_facts::enable_mir_simplification();
+    CompileBuilder::new(input.clone()).compile(|result| {
+      let (mode, summaries) = parse_header(input.lines().next().unwrap_or_default());
+      fluid_set!(EVAL_MODE, &mode);
+      fluid_set!(FLOW_SUMMARIES, summaries);
+      functions = Some(slice_functions(result.tcx));
+    });
+    let functions = functions.context("Failed to compile the test program")??;
+    ensure!(!functions.is_empty(), "The test program has no `pub fn`");
+
+    let seed = match env::var("SEED") {
+      Ok(seed) => seed.parse().context("SEED must be an integer")?,
+      Err(_) => 0,
+    };
+    let groups = generate_groups(&functions, &mut Rng(seed));
+    let source = input.clone() + &generate_main(&functions, &groups);
+    let stdout = run_program(&name, &source)?;
+    let mut lines = stdout.lines();
+
+    let mut counterexamples = Vec::new();
+    let mut reported = HashSet::<(usize, usize)>::default();
+    for group in &groups {
+      let function = &functions[group.function];
+      let output = &function.outputs[group.output];
+      let results = group
+        .calls
+        .iter()
+        .map(|call| {
+          let line = lines.next().context("The test program stopped early")?;
+          Ok((line != "<panic>").then(|| (call, line.split('\t').nth(group.output))))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+      // Only the first counterexample of each output is reported.
+      if reported.contains(&(group.function, group.output)) {
+        continue;
+      }
+
+      let mut results = results.into_iter().flatten();
+      let Some((first_call, first_output)) = results.next() else {
+        continue;
+      };
+      if let Some((call, call_output)) =
+        results.find(|(_, call_output)| *call_output != first_output)
+      {
+        let independent = output
+          .independent
+          .iter()
+          .map(|index| format!("`{}`", function.params[*index].name))
+          .collect::<Vec<_>>();
+        let counterexample = format!(
+          "{} of `{}` does not depend on {} according to its backward slice, but:\n  {} \
+           gives {}\n  {} gives {}",
+          function.output_name(output),
+          function.path,
+          independent.join(", "),
+          function.fmt_call(first_call),
+          first_output.unwrap_or_default(),
+          function.fmt_call(call),
+          call_output.unwrap_or_default(),
+        );
+        if function.known_unsound {
+          info!("Found a known counterexample: {counterexample}");
+        } else {
+          counterexamples.push(counterexample);
+        }
+        reported.insert((group.function, group.output));
+      }
+    }
+
+    for (index, function) in functions.iter().enumerate() {
+      if function.known_unsound
+        && !reported.iter().any(|(reported, _)| *reported == index)
+      {
+        counterexamples.push(format!(
+          "`{}` is known to be unsound, but no counterexample was found",
+          function.path
+        ));
+      }
+    }
+
+    if !counterexamples.is_empty() {
+      bail!(
+        "Found counterexamples with seed {seed}:\n{}",
+        counterexamples.join("\n")
+      );
+    }
+
+    Ok(())
+  };
+
+  inner().unwrap();
+}
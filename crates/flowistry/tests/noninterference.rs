@@ -0,0 +1,35 @@
+#![feature(rustc_private)]
+
+use std::{env, fs, path::PathBuf};
+
+use flowistry::test_utils;
+use test_log::test;
+
+#[test]
+fn test_noninterference() {
+  test_utils::run_tests("noninterference", |path, _| {
+    test_utils::test_noninterference(path)
+  });
+}
+
+/// Deletes a temporary file on drop, including while unwinding from a failed test.
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+  fn drop(&mut self) {
+    let _ = fs::remove_file(&self.0);
+  }
+}
+
+#[test]
+#[should_panic(expected = "`out` of `fill` has an unsupported type")]
+fn test_noninterference_unsized_mut() {
+  let file =
+    TempFile(env::temp_dir().join(format!("unsized_mut_{}.rs", std::process::id())));
+  fs::write(
+    &file.0,
+    "pub fn fill(out: &mut [u8], x: u8) {\n  out[0] = x;\n}\n",
+  )
+  .unwrap();
+  test_utils::test_noninterference(&file.0);
+}
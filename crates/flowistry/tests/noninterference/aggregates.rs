@@ -0,0 +1,22 @@
+pub fn field(t: (i32, i32), u: i32) -> i32 {
+  t.0 + u
+}
+
+pub fn field_write(t: (u8, u8), a: u8) -> (u8, u8) {
+  let mut t = t;
+  t.1 = a;
+  t
+}
+
+// The slice of an indexed place is missing the index.
+pub fn known_unsound_index(xs: [u8; 4], i: usize, other: u8) -> u8 {
+  xs[i % 4]
+}
+
+pub fn slice_sum(xs: &[i64], scale: i64, other: i64) -> i64 {
+  xs.iter().sum::<i64>() * scale
+}
+
+pub fn nested(x: &(u32, [bool; 2]), y: u32) -> bool {
+  x.1[0] && x.0 > 3
+}
@@ -0,0 +1,18 @@
+pub fn add(a: i32, b: i32, c: i32) -> i32 {
+  let _unused = c * 2;
+  a + b
+}
+
+pub fn overwrite(a: u8, b: u8) -> u8 {
+  let mut x = a;
+  x = b;
+  x
+}
+
+pub fn widths(a: i8, b: u64, c: i128, d: usize) -> i128 {
+  a as i128 + c - d as i128
+}
+
+pub fn constant(_a: i32) -> i32 {
+  42
+}
@@ -0,0 +1,21 @@
+fn set(out: &mut i32, value: i32) {
+  *out = value;
+}
+
+fn pick(a: i32, b: i32) -> i32 {
+  if a > b { a } else { b }
+}
+
+pub fn through_call(a: i32, b: i32, c: i32) -> i32 {
+  let mut x = 0;
+  set(&mut x, a);
+  x + pick(b, 1)
+}
+
+pub fn through_mut(out: &mut i32, a: i32, b: i32) {
+  set(out, a);
+}
+
+pub fn std_call(a: u32, b: u32, c: u32) -> u32 {
+  a.max(b).wrapping_mul(2)
+}
@@ -0,0 +1,28 @@
+pub fn branch(a: i32, b: i32, c: i32) -> i32 {
+  if a > 0 { b } else { 0 }
+}
+
+// The return value is missing the control dependency on `a`.
+pub fn known_unsound_early_return(a: bool, b: i32, c: i32) -> i32 {
+  if a {
+    return b;
+  }
+  let _ = c;
+  1
+}
+
+pub fn count(n: u8, step: u32, ignored: u32) -> u32 {
+  let mut total = 0;
+  for _ in 0 .. n {
+    total += step;
+  }
+  total
+}
+
+pub fn matches(a: u8, b: i32, c: i32) -> i32 {
+  match a % 3 {
+    0 => b,
+    1 => b + 1,
+    _ => -b,
+  }
+}
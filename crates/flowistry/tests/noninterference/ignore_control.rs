@@ -0,0 +1,10 @@
+/* ignorecontrol */
+
+// Ignoring control dependencies is unsound by design.
+pub fn known_unsound_branch(a: bool, b: i32) -> i32 {
+  if a { b } else { 0 }
+}
+
+pub fn direct(a: i32, b: i32) -> i32 {
+  a * 2
+}
@@ -0,0 +1,18 @@
+/* recurse */
+
+fn first(a: i32, _b: i32) -> i32 {
+  a
+}
+
+fn clear(out: &mut (i32, i32), value: i32) {
+  out.0 = value;
+}
+
+pub fn precise_call(a: i32, b: i32) -> i32 {
+  first(a, b)
+}
+
+pub fn precise_mut(out: &mut (i32, i32), a: i32, b: i32) -> i32 {
+  clear(out, a);
+  out.1 + b
+}
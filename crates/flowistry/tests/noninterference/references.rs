@@ -0,0 +1,23 @@
+pub fn write(out: &mut i32, a: i32, b: i32) {
+  *out = a;
+}
+
+pub fn accumulate(out: &mut i32, a: i32, b: i32) {
+  *out += a;
+}
+
+pub fn alias(out: &mut (i32, i32), a: i32, b: i32) -> i32 {
+  let first = &mut out.0;
+  *first = a;
+  out.1
+}
+
+pub fn read_ref(a: &i32, b: &(i32, bool)) -> i32 {
+  if b.1 { *a } else { 0 }
+}
+
+pub fn swap(x: &mut i32, y: &mut i32) {
+  let tmp = *x;
+  *x = *y;
+  *y = tmp;
+}
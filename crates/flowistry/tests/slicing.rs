@@ -7,7 +7,7 @@ use flowistry::{
   test_utils,
 };
 use rustc_span::Span;
-use rustc_utils::SpanExt;
+use rustc_utils::{SpanExt, mir::borrowck_facts::get_body_with_borrowck_facts};
 use test_log::test;
 
 fn slice(dir: &str, direction: Direction) {
@@ -34,6 +34,43 @@ fn slice(dir: &str, direction: Direction) {
   });
 }
 
+// Only outputs the dependencies that rely on a mutation which possibly happens.
+fn possible_slice(dir: &str) {
+  test_utils::run_tests(dir, |path, expected| {
+    test_utils::test_command_output(path, expected, |results, spanner, target| {
+      let tcx = results.analysis.tcx;
+      let def_id = results.analysis.def_id.expect_local();
+      let body_id = tcx.hir_body_owned_by(def_id).id();
+      let body_with_facts = get_body_with_borrowck_facts(tcx, def_id);
+      let definite_results =
+        infoflow::compute_definite_flow(tcx, body_id, body_with_facts);
+
+      let targets = spanner
+        .span_to_places(target)
+        .iter()
+        .map(|mir_span| {
+          mir_span
+            .locations
+            .iter()
+            .map(|location| (mir_span.place, *location))
+            .collect::<Vec<_>>()
+        })
+        .collect();
+      let deps = infoflow::compute_dependencies_by_certainty(
+        &results,
+        &definite_results,
+        targets,
+        Direction::Backward,
+      );
+
+      let spans = deps.iter().flat_map(|(_, possibly)| {
+        infoflow::dependency_spans(possibly, results.analysis.body, &spanner)
+      });
+      Span::merge_overlaps(spans.collect())
+    });
+  });
+}
+
 #[test]
 fn test_backward_slice() {
   slice("backward_slice", Direction::Backward);
@@ -48,3 +85,8 @@ fn test_forward_slice() {
 fn test_extensions() {
   slice("extensions", Direction::Backward);
 }
+
+#[test]
+fn test_possible_slice() {
+  possible_slice("possible_slice");
+}
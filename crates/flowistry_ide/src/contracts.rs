@@ -0,0 +1,611 @@
+//! The `check-contracts` command, which checks the flow contracts of every function in a crate.
+//!
+//! A contract is an attribute on a function that restricts which parameters flow into the
+//! function's outputs. An output is either the return value, written `return`, or the data behind
+//! a parameter of type `&mut T`, written as the name of the parameter.
+//!
+//! * `#[flowistry::no_flow(password -> return)]` means that `password` does not flow into the
+//!   return value. Several flows can be listed, e.g. `no_flow(a -> return, b -> out)`.
+//! * `#[flowistry::flows_only(out <- a, b)]` means that no parameters other than `a` and `b` flow
+//!   into `out` (besides the previous value of `out` itself). Destructured parameters like
+//!   `(b, c): (u8, u8)` cannot be named, so they never flow into `out`.
+//!
+//! The command registers `flowistry` as a tool and sets `--cfg flowistry`, so a crate can write
+//! `#[cfg_attr(flowistry, flowistry::no_flow(password -> return))]` and still build normally.
+//! Each violation is an error that points to a location where the parameter flows toward the
+//! output.
+
+use std::fmt;
+
+use anyhow::{Result, bail};
+use flowistry::{
+  extensions::{EVAL_MODE, EvalMode},
+  infoflow::{
+    self, Direction, FlowResults, compute_dependencies,
+    summaries::{FLOW_SUMMARIES, FlowSummaries},
+  },
+};
+use fluid_let::fluid_set;
+use log::debug;
+use rustc_ast::{token::TokenKind, tokenstream::TokenTree};
+use rustc_data_structures::fx::FxHashMap as HashMap;
+use rustc_hir::{AttrArgs, Attribute, BodyId, PatKind};
+use rustc_middle::{
+  mir::{Place, ProjectionElem},
+  ty::{TyCtxt, TyKind},
+};
+use rustc_span::{Span, Symbol, kw};
+use rustc_utils::{
+  BodyExt, PlaceExt, SpanExt,
+  mir::{
+    borrowck_facts::{self, get_body_with_borrowck_facts},
+    location_or_arg::LocationOrArg,
+  },
+  source_map::find_bodies::find_bodies,
+};
+
+use crate::plugin::FlowistryResult;
+
+/// An output of a function that a contract restricts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+  Return,
+  Param(Symbol),
+}
+
+impl fmt::Display for Output {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Output::Return => write!(f, "the return value"),
+      Output::Param(name) => write!(f, "`*{name}`"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Contract {
+  /// `input` does not flow into `output`.
+  NoFlow { input: Symbol, output: Output },
+
+  /// No parameters other than `inputs` flow into `output`.
+  FlowsOnly { output: Output, inputs: Vec<Symbol> },
+}
+
+/// The tokens of a contract, without spans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContractToken {
+  Ident(Symbol),
+  RArrow,
+  LArrow,
+  Comma,
+  Other,
+}
+
+fn output(name: Symbol) -> Output {
+  if name == kw::Return {
+    Output::Return
+  } else {
+    Output::Param(name)
+  }
+}
+
+fn input(token: ContractToken) -> Result<Symbol> {
+  match token {
+    ContractToken::Ident(name) if name != kw::Return => Ok(name),
+    _ => bail!("expected the name of a parameter"),
+  }
+}
+
+/// Parses the contracts of an attribute, where `kind` is `no_flow` or `flows_only`.
+fn parse_contracts(kind: Symbol, tokens: &[ContractToken]) -> Result<Vec<Contract>> {
+  use ContractToken::*;
+  let mut contracts = Vec::new();
+  if kind.as_str() == "no_flow" {
+    for flow in tokens.split(|token| *token == Comma) {
+      match flow {
+        [] => {}
+        [from, RArrow, Ident(to)] => contracts.push(Contract::NoFlow {
+          input: input(*from)?,
+          output: output(*to),
+        }),
+        _ => bail!("expected flows of the form `parameter -> output`"),
+      }
+    }
+  } else {
+    let [Ident(to), LArrow, inputs @ ..] = tokens else {
+      bail!("expected a contract of the form `output <- parameter, ...`");
+    };
+    let inputs = inputs
+      .split(|token| *token == Comma)
+      .filter(|input| !input.is_empty())
+      .map(|input_tokens| match input_tokens {
+        [token] => input(*token),
+        _ => bail!("expected a comma-separated list of parameters"),
+      })
+      .collect::<Result<Vec<_>>>()?;
+    contracts.push(Contract::FlowsOnly {
+      output: output(*to),
+      inputs,
+    });
+  }
+  Ok(contracts)
+}
+
+/// Returns the contracts declared by an attribute, if it is a contract.
+fn attr_contracts(attr: &Attribute) -> Option<Result<Vec<Contract>>> {
+  let Attribute::Unparsed(item) = attr else {
+    return None;
+  };
+  let [tool, kind] = item.path.segments[..] else {
+    return None;
+  };
+  if tool.name.as_str() != "flowistry"
+    || !matches!(kind.name.as_str(), "no_flow" | "flows_only")
+  {
+    return None;
+  }
+  let AttrArgs::Delimited(args) = &item.args else {
+    return Some(Err(anyhow::anyhow!("expected arguments")));
+  };
+  let tokens = args
+    .tokens
+    .iter()
+    .map(|tree| match tree {
+      TokenTree::Token(token, _) => match token.kind {
+        TokenKind::Ident(name, _) => ContractToken::Ident(name),
+        TokenKind::RArrow => ContractToken::RArrow,
+        TokenKind::LArrow => ContractToken::LArrow,
+        TokenKind::Comma => ContractToken::Comma,
+        _ => ContractToken::Other,
+      },
+      TokenTree::Delimited(..) => ContractToken::Other,
+    })
+    .collect::<Vec<_>>();
+  Some(parse_contracts(kind.name, &tokens))
+}
+
+/// An error in the contracts of a function.
+#[derive(Debug)]
+enum ContractError {
+  /// The contract declared at `attr_span` is not valid for the function.
+  Invalid { attr_span: Span, message: String },
+
+  /// `input` flows into `output` toward `witness`, which the contract declared at `attr_span`
+  /// forbids. `input` is the name of the parameter, or the source of its pattern if it is
+  /// destructured.
+  Violation {
+    input: String,
+    output: Output,
+    witness: Span,
+    attr_span: Span,
+  },
+}
+
+impl ContractError {
+  fn emit(&self, tcx: TyCtxt) {
+    match self {
+      ContractError::Invalid { attr_span, message } => {
+        tcx.dcx().span_err(*attr_span, message.clone());
+      }
+      ContractError::Violation {
+        input,
+        output,
+        witness,
+        attr_span,
+      } => {
+        tcx
+          .dcx()
+          .struct_span_err(
+            *witness,
+            format!("`{input}` flows into {output}, which violates a flow contract"),
+          )
+          .with_span_label(*witness, format!("`{input}` flows toward {output} here"))
+          .with_span_note(*attr_span, "the contract is declared here")
+          .emit();
+      }
+    }
+  }
+}
+
+/// Checks the contracts of a body.
+fn check_body<'tcx>(
+  tcx: TyCtxt<'tcx>,
+  body_id: BodyId,
+  results: &FlowResults<'_, 'tcx>,
+  contracts: &[(Contract, Span)],
+) -> Vec<ContractError> {
+  let body = results.analysis.body;
+  let body_span = tcx.hir_span_with_body(tcx.hir_body_owner(body_id));
+  let source_map = tcx.sess.source_map();
+
+  // Every parameter with its local and its name, which is the source of its pattern if it is
+  // destructured. Closures have their environment as an extra first argument.
+  let hir_params = tcx.hir_body(body_id).params;
+  let all_params = hir_params
+    .iter()
+    .zip(body.args_iter().skip(body.arg_count - hir_params.len()))
+    .map(|(param, local)| {
+      let name = match param.pat.kind {
+        PatKind::Binding(_, _, ident, _) => ident.name.to_string(),
+        _ => source_map
+          .span_to_snippet(param.pat.span)
+          .unwrap_or_else(|_| format!("argument {}", local.as_usize())),
+      };
+      (local, name)
+    })
+    .collect::<Vec<_>>();
+  let params = hir_params
+    .iter()
+    .zip(&all_params)
+    .filter_map(|(param, (local, _))| match param.pat.kind {
+      PatKind::Binding(_, _, ident, _) => Some((ident.name, *local)),
+      _ => None,
+    })
+    .collect::<HashMap<_, _>>();
+
+  let mut errors = Vec::new();
+  for (contract, attr_span) in contracts {
+    let mut error = |message: String| {
+      errors.push(ContractError::Invalid {
+        attr_span: *attr_span,
+        message,
+      });
+    };
+    let (output, named) = match contract {
+      Contract::NoFlow { input, output } => (*output, vec![*input]),
+      Contract::FlowsOnly { output, inputs } => (*output, inputs.clone()),
+    };
+    if let Some(name) = named.iter().find(|name| !params.contains_key(name)) {
+      error(format!("`{name}` is not a parameter"));
+      continue;
+    }
+
+    let output_place = match output {
+      Output::Return => Place::return_place(),
+      Output::Param(name) => {
+        let Some(local) = params.get(&name) else {
+          error(format!("`{name}` is not a parameter"));
+          continue;
+        };
+        let is_mut_ref = matches!(
+          body.local_decls[*local].ty.kind(),
+          TyKind::Ref(_, _, mutability) | TyKind::RawPtr(_, mutability) if mutability.is_mut()
+        );
+        if !is_mut_ref {
+          error(format!(
+            "`{name}` is not a mutable reference, so nothing flows into it"
+          ));
+          continue;
+        }
+        Place::from_local(*local, tcx).project_deeper(&[ProjectionElem::Deref], tcx)
+      }
+    };
+
+    // `flows_only` forbids every other parameter, including destructured ones.
+    let forbidden = match contract {
+      Contract::NoFlow { .. } => named
+        .iter()
+        .map(|name| (params[name], name.to_string()))
+        .collect::<Vec<_>>(),
+      Contract::FlowsOnly { .. } => {
+        let allowed = named
+          .iter()
+          .chain(match &output {
+            Output::Param(name) => Some(name),
+            Output::Return => None,
+          })
+          .map(|name| params[name])
+          .collect::<Vec<_>>();
+        all_params
+          .iter()
+          .filter(|(local, _)| !allowed.contains(local))
+          .cloned()
+          .collect()
+      }
+    };
+
+    let targets = body
+      .all_returns()
+      .map(|location| (output_place, LocationOrArg::Location(location)))
+      .collect::<Vec<_>>();
+    let output_deps =
+      compute_dependencies(results, vec![targets], Direction::Backward).remove(0);
+
+    // `params` only holds parameter locals, so `Arg` inputs for referenced globals are
+    // never forbidden: contracts cannot name globals.
+    let mut forbidden = forbidden
+      .into_iter()
+      .filter(|(local, _)| output_deps.contains(LocationOrArg::Arg(*local)))
+      .collect::<Vec<_>>();
+    forbidden.sort_by_key(|(local, _)| *local);
+    for (local, name) in forbidden {
+      // The witness is the first location on a path from the parameter to the output.
+      let input_deps = compute_dependencies(
+        results,
+        vec![vec![(
+          Place::from_local(local, tcx),
+          LocationOrArg::Arg(local),
+        )]],
+        Direction::Forward,
+      )
+      .remove(0);
+      let witness = input_deps
+        .iter()
+        .filter(|location| output_deps.contains(**location))
+        .filter_map(|location| match location {
+          LocationOrArg::Location(location) => Some(*location),
+          LocationOrArg::Arg(_) => None,
+        })
+        .min();
+      debug!("Contract {contract:?} is violated by {name} at {witness:?}");
+      let witness = match witness {
+        Some(location) => {
+          let span = body.source_info(location).span;
+          span.as_local(body_span).unwrap_or(span)
+        }
+        None => body.local_decls[local].source_info.span,
+      };
+      errors.push(ContractError::Violation {
+        input: name,
+        output,
+        witness,
+        attr_span: *attr_span,
+      });
+    }
+  }
+  errors
+}
+
+/// Checks the contracts of every function in the crate.
+fn check_crate(tcx: TyCtxt) -> Vec<ContractError> {
+  let mut errors = Vec::new();
+  for (_, body_id) in find_bodies(tcx) {
+    let def_id = tcx.hir_body_owner_def_id(body_id);
+    let mut contracts = Vec::new();
+    for attr in tcx.hir_attrs(tcx.local_def_id_to_hir_id(def_id)) {
+      match attr_contracts(attr) {
+        Some(Ok(attr_contracts)) => contracts.extend(
+          attr_contracts
+            .into_iter()
+            .map(|contract| (contract, attr.span())),
+        ),
+        Some(Err(error)) => errors.push(ContractError::Invalid {
+          attr_span: attr.span(),
+          message: format!("invalid flow contract: {error}"),
+        }),
+        None => {}
+      }
+    }
+    if contracts.is_empty() {
+      continue;
+    }
+
+    debug!("Checking contracts of {}", tcx.def_path_str(def_id));
+    let body_with_facts = get_body_with_borrowck_facts(tcx, def_id);
+    let results = infoflow::compute_flow(tcx, body_id, body_with_facts);
+    errors.extend(check_body(tcx, body_id, &results, &contracts));
+  }
+  errors
+}
+
+struct Callbacks {
+  eval_mode: Option<EvalMode>,
+  summaries: Option<FlowSummaries>,
+
+  /// The number of violations and invalid contracts.
+  errors: usize,
+}
+
+impl rustc_driver::Callbacks for Callbacks {
+  fn config(&mut self, config: &mut rustc_interface::Config) {
+    borrowck_facts::enable_mir_simplification();
+    config.override_queries = Some(borrowck_facts::override_queries);
+  }
+
+  fn after_analysis<'tcx>(
+    &mut self,
+    _compiler: &rustc_interface::interface::Compiler,
+    tcx: TyCtxt<'tcx>,
+  ) -> rustc_driver::Compilation {
+    fluid_set!(EVAL_MODE, self.eval_mode.unwrap_or_default());
+    fluid_set!(FLOW_SUMMARIES, self.summaries.take().unwrap_or_default());
+
+    for error in check_crate(tcx) {
+      error.emit(tcx);
+      self.errors += 1;
+    }
+    FLOW_SUMMARIES.get(|summaries| summaries.map(FlowSummaries::warn_unused));
+
+    rustc_driver::Compilation::Stop
+  }
+}
+
+/// Checks the flow contracts of every function in the crate. Violations are emitted as errors,
+/// which fail the build.
+pub fn check_contracts(args: &[String]) -> FlowistryResult<()> {
+  let mut args = args.to_vec();
+  args.extend([
+    "--cfg=flowistry".to_string(),
+    "--check-cfg=cfg(flowistry)".to_string(),
+    "-Zcrate-attr=feature(register_tool)".to_string(),
+    "-Zcrate-attr=register_tool(flowistry)".to_string(),
+  ]);
+  let mut callbacks = Callbacks {
+    eval_mode: EVAL_MODE.copied(),
+    summaries: FLOW_SUMMARIES.get(|summaries| summaries.cloned()),
+    errors: 0,
+  };
+  crate::plugin::run_with_callbacks(&args, &mut callbacks)?;
+  if callbacks.errors == 0 {
+    eprintln!("All flow contracts hold.");
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use rustc_utils::test_utils::CompileBuilder;
+
+  use super::*;
+
+  /// Checks the contracts of `input`, and returns each error as a message with the line and
+  /// the source snippet of its span: the witness for violations, and the attribute otherwise.
+  fn contract_errors(input: &str) -> Vec<(String, usize, String)> {
+    let mut errors = Vec::new();
+    borrowck_facts::enable_mir_simplification();
+    CompileBuilder::new(input)
+      .with_args([
+        "--cfg=flowistry".to_string(),
+        "-Zcrate-attr=feature(register_tool)".to_string(),
+        "-Zcrate-attr=register_tool(flowistry)".to_string(),
+      ])
+      .compile(|result| {
+        let tcx = result.tcx;
+        let source_map = tcx.sess.source_map();
+        let line = |span: Span| source_map.lookup_char_pos(span.lo()).line;
+        let snippet = |span: Span| source_map.span_to_snippet(span).unwrap();
+        errors = check_crate(tcx)
+          .into_iter()
+          .map(|error| match error {
+            ContractError::Invalid { attr_span, message } => {
+              (message, line(attr_span), snippet(attr_span))
+            }
+            ContractError::Violation {
+              input,
+              output,
+              witness,
+              ..
+            } => (
+              format!("`{input}` flows into {output}"),
+              line(witness),
+              snippet(witness),
+            ),
+          })
+          .collect();
+      });
+    errors
+  }
+
+  #[test]
+  fn test_check_contracts() {
+    let input = r#"
+#[flowistry::no_flow(password -> return)]
+fn no_flow_holds(user: &str, password: &str) -> usize {
+  let _ = password;
+  user.len()
+}
+
+#[flowistry::no_flow(password -> return)]
+fn no_flow_violated(user: &str, password: &str) -> usize {
+  let n = user.len();
+  n + password.len()
+}
+
+#[flowistry::flows_only(out <- a)]
+fn flows_only_holds(out: &mut i32, a: i32, b: i32) {
+  *out += a;
+  let _ = b;
+}
+
+#[flowistry::flows_only(out <- a)]
+fn flows_only_violated(out: &mut i32, a: i32, b: i32) {
+  *out = a;
+  *out += b * 2;
+}
+
+#[flowistry::no_flow(key -> return)]
+fn not_a_parameter(password: &str) -> usize {
+  password.len()
+}
+
+#[flowistry::flows_only(out <- a)]
+fn not_mutable(out: &i32, a: i32) {}
+
+#[flowistry::flows_only(out <- a)]
+fn destructured(out: &mut u8, a: u8, (b, c): (u8, u8)) {
+  *out = a + b + c;
+}
+"#;
+    let error = |message: &str, line: usize, snippet: &str| {
+      (message.to_string(), line, snippet.to_string())
+    };
+    assert_eq!(contract_errors(input), vec![
+      error("`password` flows into the return value", 11, "password"),
+      error("`b` flows into `*out`", 23, "b"),
+      error(
+        "`key` is not a parameter",
+        26,
+        "#[flowistry::no_flow(key -> return)]"
+      ),
+      error(
+        "`out` is not a mutable reference, so nothing flows into it",
+        31,
+        "#[flowistry::flows_only(out <- a)]"
+      ),
+      error("`(b, c)` flows into `*out`", 35, "b"),
+    ]);
+  }
+
+  #[test]
+  fn test_parse_contracts() {
+    rustc_span::create_default_session_globals_then(|| {
+      use ContractToken::*;
+      let [password, out, a, b] = ["password", "out", "a", "b"].map(Symbol::intern);
+
+      assert_eq!(
+        parse_contracts(Symbol::intern("no_flow"), &[
+          Ident(password),
+          RArrow,
+          Ident(kw::Return),
+          Comma,
+          Ident(a),
+          RArrow,
+          Ident(out),
+        ])
+        .unwrap(),
+        vec![
+          Contract::NoFlow {
+            input: password,
+            output: Output::Return
+          },
+          Contract::NoFlow {
+            input: a,
+            output: Output::Param(out)
+          },
+        ]
+      );
+
+      assert_eq!(
+        parse_contracts(Symbol::intern("flows_only"), &[
+          Ident(out),
+          LArrow,
+          Ident(a),
+          Comma,
+          Ident(b),
+        ])
+        .unwrap(),
+        vec![Contract::FlowsOnly {
+          output: Output::Param(out),
+          inputs: vec![a, b],
+        }]
+      );
+
+      assert!(
+        parse_contracts(Symbol::intern("no_flow"), &[
+          Ident(kw::Return),
+          RArrow,
+          Ident(a)
+        ])
+        .is_err()
+      );
+      assert!(
+        parse_contracts(Symbol::intern("flows_only"), &[
+          Ident(out),
+          RArrow,
+          Ident(a)
+        ])
+        .is_err()
+      );
+    });
+  }
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use flowistry::infoflow::{self, Direction};
+use flowistry::infoflow::{self, Direction, caveats::CaveatKind};
 use itertools::Itertools;
 use rustc_hir::BodyId;
 use rustc_middle::ty::TyCtxt;
@@ -21,13 +21,23 @@ pub struct PlaceInfo {
   pub range: CharRange,
   pub ranges: Vec<CharRange>,
   pub slice: Vec<CharRange>,
+  pub definite_slice: Vec<CharRange>,
+  pub possible_slice: Vec<CharRange>,
   pub direct_influence: Vec<CharRange>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct Caveat {
+  pub kind: CaveatKind,
+  pub range: CharRange,
+  pub description: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct FocusOutput {
   pub place_info: Vec<PlaceInfo>,
   pub containers: Vec<CharRange>,
+  pub caveats: Vec<Caveat>,
 }
 
 pub fn focus(tcx: TyCtxt, body_id: BodyId) -> Result<FocusOutput> {
@@ -35,6 +45,12 @@ pub fn focus(tcx: TyCtxt, body_id: BodyId) -> Result<FocusOutput> {
   let body_with_facts = get_body_with_borrowck_facts(tcx, def_id);
   let body = &body_with_facts.body;
   let results = &infoflow::compute_flow(tcx, body_id, body_with_facts);
+  // Only rerun the analysis for definite flows if some flow is not definite.
+  let definite_results = results
+    .analysis
+    .has_possible_mutations()
+    .then(|| infoflow::compute_definite_flow(tcx, body_id, body_with_facts));
+  let definite_results = definite_results.as_ref().unwrap_or(results);
 
   let source_map = tcx.sess.source_map();
   let spanner = Spanner::new(tcx, body_id, body);
@@ -62,8 +78,12 @@ pub fn focus(tcx: TyCtxt, body_id: BodyId) -> Result<FocusOutput> {
     .map(|(_, target)| target.clone())
     .collect();
 
-  let relevant =
-    infoflow::compute_dependency_spans(results, targets, Direction::Both, &spanner);
+  let relevant = infoflow::compute_dependencies_by_certainty(
+    results,
+    definite_results,
+    targets,
+    Direction::Both,
+  );
 
   let direct =
     direct_influence::DirectInfluence::build(body, &results.analysis.place_info);
@@ -71,9 +91,23 @@ pub fn focus(tcx: TyCtxt, body_id: BodyId) -> Result<FocusOutput> {
   let slices = grouped_spans
     .iter()
     .zip(relevant)
-    .filter_map(|((mir_span, targets), relevant)| {
+    .filter_map(|((mir_span, targets), (definitely, possibly))| {
+      let mut all = definitely.clone();
+      all.union(&possibly);
+      let relevant = infoflow::dependency_spans(&all, body, &spanner);
       log::debug!("Slice for {mir_span:?} is {relevant:#?}");
 
+      // A span can contain locations of both certainties, in which case it is definitely relevant.
+      let definite_slice = infoflow::dependency_spans(&definitely, body, &spanner);
+      let possible_slice = infoflow::dependency_spans(&possibly, body, &spanner)
+        .into_iter()
+        .filter(|span| {
+          !definite_slice
+            .iter()
+            .any(|def_span| def_span.contains(*span))
+        })
+        .collect::<Vec<_>>();
+
       let direct_influence = targets
         .iter()
         .flat_map(|(target, _)| direct.lookup(*target))
@@ -99,6 +133,8 @@ pub fn focus(tcx: TyCtxt, body_id: BodyId) -> Result<FocusOutput> {
         range: CharRange::from_span(mir_span.span(), source_map).ok()?,
         ranges: to_ranges(vec![mir_span.span()]),
         slice: to_ranges(slice),
+        definite_slice: to_ranges(definite_slice),
+        possible_slice: to_ranges(possible_slice),
         direct_influence: to_ranges(direct_influence),
       })
     })
@@ -118,8 +154,22 @@ pub fn focus(tcx: TyCtxt, body_id: BodyId) -> Result<FocusOutput> {
     containers.push(CharRange::from_span(sp, source_map)?);
   }
 
+  let caveats = results
+    .analysis
+    .caveats()
+    .into_iter()
+    .filter_map(|caveat| {
+      Some(Caveat {
+        kind: caveat.kind,
+        range: CharRange::from_span(caveat.span, source_map).ok()?,
+        description: caveat.description,
+      })
+    })
+    .collect();
+
   Ok(FocusOutput {
     place_info: slices,
     containers,
+    caveats,
   })
 }
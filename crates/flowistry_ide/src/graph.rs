@@ -0,0 +1,42 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use flowistry::infoflow::{self, graph::DependenceGraph};
+use rustc_hir::BodyId;
+use rustc_middle::ty::TyCtxt;
+use rustc_utils::mir::borrowck_facts::get_body_with_borrowck_facts;
+use serde::{Deserialize, Serialize};
+
+/// The serialization format for the `graph` command.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum GraphFormat {
+  Dot,
+  Json,
+}
+
+impl FromStr for GraphFormat {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "dot" => Ok(Self::Dot),
+      "json" => Ok(Self::Json),
+      _ => Err(format!("Could not parse: {s}")),
+    }
+  }
+}
+
+pub fn graph(
+  tcx: TyCtxt,
+  body_id: BodyId,
+  format: GraphFormat,
+  with_places: bool,
+) -> Result<String> {
+  let def_id = tcx.hir_body_owner_def_id(body_id);
+  let body_with_facts = get_body_with_borrowck_facts(tcx, def_id);
+  let results = infoflow::compute_flow(tcx, body_id, body_with_facts);
+  let graph = DependenceGraph::build(&results, with_places);
+  Ok(match format {
+    GraphFormat::Dot => graph.to_dot(),
+    GraphFormat::Json => graph.to_json(),
+  })
+}
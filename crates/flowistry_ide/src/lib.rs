@@ -8,6 +8,7 @@
 )]
 
 extern crate either;
+extern crate rustc_ast;
 extern crate rustc_data_structures;
 extern crate rustc_driver;
 extern crate rustc_errors;
@@ -21,11 +22,14 @@ extern crate rustc_mir_dataflow;
 extern crate rustc_serialize;
 extern crate rustc_span;
 
+mod contracts;
 #[cfg(feature = "decompose")]
 mod decompose;
 mod focus;
+mod graph;
 mod playground;
 mod plugin;
+mod query;
 mod spans;
 
 pub use plugin::FlowistryPlugin;
@@ -1,6 +1,6 @@
 use std::{
   borrow::Cow,
-  env,
+  env, fs,
   path::PathBuf,
   process::{Command, exit},
   time::Instant,
@@ -9,8 +9,11 @@ use std::{
 use anyhow::Context;
 use base64::Engine;
 use clap::{Parser, Subcommand};
-use flowistry::extensions::{
-  ContextMode, EVAL_MODE, EvalMode, MutabilityMode, PointerMode,
+use flowistry::{
+  extensions::{
+    AliasMode, ContextMode, ControlMode, EVAL_MODE, EvalMode, MutabilityMode, PointerMode,
+  },
+  infoflow::summaries::{DEFAULT_SUMMARIES_FILE, FLOW_SUMMARIES, FlowSummaries},
 };
 use fluid_let::fluid_set;
 use log::{debug, info};
@@ -18,7 +21,7 @@ use rustc_hir::BodyId;
 use rustc_interface::interface::Result as RustcResult;
 use rustc_middle::ty::TyCtxt;
 use rustc_plugin::{CrateFilter, RustcPlugin, RustcPluginArgs, Utf8Path};
-use rustc_span::ErrorGuaranteed;
+use rustc_span::{ErrorGuaranteed, fatal_error::FatalError};
 use rustc_utils::{
   mir::borrowck_facts,
   source_map::{
@@ -30,6 +33,8 @@ use rustc_utils::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::graph::GraphFormat;
+
 #[derive(Parser, Serialize, Deserialize)]
 pub struct FlowistryPluginArgs {
   #[clap(long)]
@@ -41,6 +46,15 @@ pub struct FlowistryPluginArgs {
   mutability_mode: Option<MutabilityMode>,
   #[clap(long)]
   pointer_mode: Option<PointerMode>,
+  #[clap(long)]
+  alias_mode: Option<AliasMode>,
+  #[clap(long)]
+  control_mode: Option<ControlMode>,
+
+  /// JSON file of flow summaries for external functions, defaults to
+  /// `flowistry-summaries.json` in the workspace root if it exists.
+  #[clap(long)]
+  summaries: Option<PathBuf>,
 
   #[clap(subcommand)]
   command: FlowistryCommand,
@@ -63,6 +77,28 @@ enum FlowistryCommand {
     pos: usize,
   },
 
+  /// Prints the dependence graph of the function containing the given (1-based) line.
+  Graph {
+    file: String,
+    line: usize,
+    #[clap(long, default_value = "dot")]
+    format: GraphFormat,
+    /// Include a node for each place, in addition to each location
+    #[clap(long)]
+    places: bool,
+  },
+
+  /// Answers Datalog queries over the flow facts of the crate containing the given file.
+  ///
+  /// The program is either a path to a file or the text of the program itself.
+  Query {
+    file: String,
+    program: String,
+    /// Print the answers as JSON
+    #[clap(long)]
+    json: bool,
+  },
+
   Playground {
     file: String,
     start_line: usize,
@@ -71,11 +107,28 @@ enum FlowistryCommand {
     end_column: usize,
   },
 
+  /// Checks the flow contracts of every function in the workspace, such as
+  /// `#[flowistry::no_flow(password -> return)]`. See the `contracts` module for details.
+  CheckContracts,
+
   Preload,
 
   RustcVersion,
 }
 
+/// Returns the root directory of the workspace that contains the current directory.
+fn workspace_root(cargo_path: &str) -> Option<PathBuf> {
+  let output = Command::new(cargo_path)
+    .args(["locate-project", "--workspace", "--message-format", "plain"])
+    .output()
+    .ok()?;
+  if !output.status.success() {
+    return None;
+  }
+  let manifest_path = PathBuf::from(String::from_utf8(output.stdout).ok()?.trim());
+  Some(manifest_path.parent()?.to_path_buf())
+}
+
 pub struct FlowistryPlugin;
 impl RustcPlugin for FlowistryPlugin {
   type Args = FlowistryPluginArgs;
@@ -89,10 +142,16 @@ impl RustcPlugin for FlowistryPlugin {
   }
 
   fn args(&self, target_dir: &Utf8Path) -> RustcPluginArgs<FlowistryPluginArgs> {
-    let args = FlowistryPluginArgs::parse_from(env::args().skip(1));
+    let mut args = FlowistryPluginArgs::parse_from(env::args().skip(1));
 
     let cargo_path = env::var("CARGO_PATH").unwrap_or_else(|_| "cargo".to_string());
 
+    if args.summaries.is_none() {
+      args.summaries = workspace_root(&cargo_path)
+        .map(|root| root.join(DEFAULT_SUMMARIES_FILE))
+        .filter(|path| path.exists());
+    }
+
     use FlowistryCommand::*;
     match &args.command {
       Preload => {
@@ -110,6 +169,12 @@ impl RustcPlugin for FlowistryPlugin {
         println!("{version_str}");
         exit(0);
       }
+      CheckContracts => {
+        return RustcPluginArgs {
+          filter: CrateFilter::OnlyWorkspace,
+          args,
+        };
+      }
       _ => {}
     };
 
@@ -117,6 +182,8 @@ impl RustcPlugin for FlowistryPlugin {
       Spans { file, .. } => file,
       Focus { file, .. } => file,
       Decompose { file, .. } => file,
+      Graph { file, .. } => file,
+      Query { file, .. } => file,
       Playground { file, .. } => file,
       _ => unreachable!(),
     };
@@ -138,12 +205,34 @@ impl RustcPlugin for FlowistryPlugin {
         .mutability_mode
         .unwrap_or(MutabilityMode::DistinguishMut),
       pointer_mode: plugin_args.pointer_mode.unwrap_or(PointerMode::Precise),
+      alias_mode: plugin_args.alias_mode.unwrap_or(AliasMode::FlowInsensitive),
+      control_mode: plugin_args.control_mode.unwrap_or(ControlMode::Include),
     };
     fluid_set!(EVAL_MODE, eval_mode);
 
+    let summaries = match plugin_args.summaries {
+      Some(path) => match FlowSummaries::load(&path) {
+        Ok(summaries) => summaries,
+        Err(error) => {
+          return postprocess::<()>(Err(FlowistryError::AnalysisError {
+            error: format!("{error:?}"),
+          }));
+        }
+      },
+      None => FlowSummaries::default(),
+    };
+    fluid_set!(FLOW_SUMMARIES, summaries);
+
     use FlowistryCommand::*;
     match plugin_args.command {
       Spans { file, .. } => postprocess(crate::spans::spans(&compiler_args, file)),
+      // Violations are reported as diagnostics and fail the build like other errors,
+      // so the result is not encoded.
+      CheckContracts => match crate::contracts::check_contracts(&compiler_args) {
+        Ok(()) => Ok(()),
+        Err(FlowistryError::BuildError) => FatalError.raise(),
+        Err(err) => postprocess::<()>(Err(err)),
+      },
       Playground {
         file,
         start_line,
@@ -190,6 +279,58 @@ impl RustcPlugin for FlowistryPlugin {
         };
         postprocess(run(crate::focus::focus, compute_target, &compiler_args))
       }
+      Graph {
+        file,
+        line,
+        format,
+        places,
+      } => {
+        let compute_target = || {
+          let cpos = CharPos {
+            line: line.saturating_sub(1),
+            column: 0,
+          };
+          FunctionIdentifier::Range(CharRange {
+            start: cpos,
+            end: cpos,
+            filename: Filename::intern(&file),
+          })
+        };
+        let graph = move |tcx: TyCtxt, body_id: BodyId| {
+          crate::graph::graph(tcx, body_id, format, places)
+        };
+        // The graph is printed as-is rather than encoded, since it is meant for other tools.
+        match run(graph, compute_target, &compiler_args) {
+          Ok(output) => {
+            print!("{output}");
+            Ok(())
+          }
+          Err(err) => postprocess::<()>(Err(err)),
+        }
+      }
+      Query { program, json, .. } => {
+        let program = fs::read_to_string(&program).unwrap_or(program);
+        let result = program
+          .parse::<crate::query::Program>()
+          .map_err(|e| FlowistryError::AnalysisError {
+            error: format!("{e:?}"),
+          })
+          .and_then(|program| crate::query::query(&compiler_args, program));
+        // Like the graph, the answers are printed as-is rather than encoded.
+        match result {
+          Ok(results) if json => {
+            println!("{}", serde_json::to_string_pretty(&results).unwrap());
+            Ok(())
+          }
+          Ok(results) => {
+            for result in results {
+              println!("{result}");
+            }
+            Ok(())
+          }
+          Err(err) => postprocess::<()>(Err(err)),
+        }
+      }
       Decompose {
         file: _file,
         pos: _pos,
@@ -265,6 +406,7 @@ fn run<A: FlowistryAnalysis, T: ToSpan>(
     output: None,
     rustc_start: Instant::now(),
     eval_mode: EVAL_MODE.copied(),
+    summaries: FLOW_SUMMARIES.get(|summaries| summaries.cloned()),
   };
 
   info!("Starting rustc analysis...");
@@ -314,6 +456,7 @@ struct FlowistryCallbacks<A: FlowistryAnalysis, T: ToSpan, F: FnOnce() -> T> {
   output: Option<anyhow::Result<A::Output>>,
   rustc_start: Instant,
   eval_mode: Option<EvalMode>,
+  summaries: Option<FlowSummaries>,
 }
 
 impl<A: FlowistryAnalysis, T: ToSpan, F: FnOnce() -> T> rustc_driver::Callbacks
@@ -331,6 +474,7 @@ impl<A: FlowistryAnalysis, T: ToSpan, F: FnOnce() -> T> rustc_driver::Callbacks
   ) -> rustc_driver::Compilation {
     elapsed("rustc", self.rustc_start);
     fluid_set!(EVAL_MODE, self.eval_mode.unwrap_or_default());
+    fluid_set!(FLOW_SUMMARIES, self.summaries.take().unwrap_or_default());
 
     let mut analysis = self.analysis.take().unwrap();
     self.output = Some((|| {
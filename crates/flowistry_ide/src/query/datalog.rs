@@ -0,0 +1,919 @@
+//! A small Datalog dialect for querying [`Facts`].
+//!
+//! A program is a sequence of rules and queries over the relations in [`RELATIONS`]:
+//!
+//! ```text
+//! % Arguments of a sensitive type
+//! secret(F, A) :- arg(F, A, _, T), contains(T, "Password").
+//!
+//! % Calls that a secret flows into
+//! leak(F, L, C) :- secret(F, A), flows(F, A, L), call(F, L, C), starts_with(C, "log::").
+//!
+//! ?- leak(F, L, C), location(F, L, _, Source).
+//! ```
+//!
+//! Variables start with an uppercase letter or an underscore, and `_` alone is a wildcard.
+//! Constants are either double-quoted strings or bare numbers, and are compared as strings.
+//! A body literal is an atom, a negated atom (`!atom` or `not atom`), a comparison `X = Y`
+//! or `X != Y`, or one of the string predicates `contains`, `starts_with` and `ends_with`.
+//! Comments start with `%` or `//` and run to the end of the line.
+//!
+//! Rules are evaluated bottom-up to a fixpoint, and negation must be stratified, i.e. a relation
+//! cannot depend on its own negation. Every variable in a rule's head, in a negated atom or in a
+//! comparison must also appear in a positive atom of the body.
+
+use std::{
+  collections::{BTreeSet, HashMap, HashSet},
+  fmt,
+  str::FromStr,
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use flowistry::infoflow::facts::{Facts, RELATIONS};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Term {
+  Var(String),
+  Const(String),
+  Wildcard,
+}
+
+#[derive(Debug, Clone)]
+struct Atom {
+  relation: String,
+  terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Builtin {
+  Eq,
+  Neq,
+  Contains,
+  StartsWith,
+  EndsWith,
+}
+
+impl Builtin {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "contains" => Some(Builtin::Contains),
+      "starts_with" => Some(Builtin::StartsWith),
+      "ends_with" => Some(Builtin::EndsWith),
+      _ => None,
+    }
+  }
+
+  fn holds(self, lhs: &str, rhs: &str) -> bool {
+    match self {
+      Builtin::Eq => lhs == rhs,
+      Builtin::Neq => lhs != rhs,
+      Builtin::Contains => lhs.contains(rhs),
+      Builtin::StartsWith => lhs.starts_with(rhs),
+      Builtin::EndsWith => lhs.ends_with(rhs),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+enum Literal {
+  Positive(Atom),
+  Negative(Atom),
+  Builtin(Builtin, Term, Term),
+}
+
+impl Literal {
+  fn terms(&self) -> Vec<&Term> {
+    match self {
+      Literal::Positive(atom) | Literal::Negative(atom) => atom.terms.iter().collect(),
+      Literal::Builtin(_, lhs, rhs) => vec![lhs, rhs],
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+  head: Atom,
+  body: Vec<Literal>,
+  line: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Query {
+  text: String,
+  body: Vec<Literal>,
+  line: usize,
+}
+
+/// A parsed Datalog program, see the [module-level documentation](self).
+#[derive(Debug, Clone)]
+pub struct Program {
+  rules: Vec<Rule>,
+  queries: Vec<Query>,
+}
+
+/// The answers to one query of a [`Program`].
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResult {
+  /// The query as written in the program.
+  pub query: String,
+  /// The variables of the query, in order of first appearance.
+  pub columns: Vec<String>,
+  /// The distinct bindings of `columns` that satisfy the query, in sorted order.
+  pub rows: Vec<Vec<String>>,
+}
+
+impl fmt::Display for QueryResult {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "?- {}", self.query)?;
+    for row in &self.rows {
+      if row.is_empty() {
+        writeln!(f, "  yes")?;
+        continue;
+      }
+      let bindings = self
+        .columns
+        .iter()
+        .zip(row)
+        .map(|(column, value)| format!("{column} = {value:?}"))
+        .collect::<Vec<_>>();
+      writeln!(f, "  {}", bindings.join(", "))?;
+    }
+    write!(f, "({} results)", self.rows.len())
+  }
+}
+
+impl Program {
+  /// Evaluates the rules of the program over `facts` and returns the answers to each query.
+  pub fn evaluate(&self, facts: &Facts) -> Result<Vec<QueryResult>> {
+    let strata = self.check()?;
+
+    let mut derived = Facts::default();
+    for stratum in strata {
+      let rules = stratum.iter().map(|i| &self.rules[*i]).collect::<Vec<_>>();
+      let recursive = rules
+        .iter()
+        .map(|rule| rule.head.relation.as_str())
+        .collect::<HashSet<_>>();
+
+      // Semi-naive evaluation: after the first round, each rule only needs to
+      // consider derivations that use at least one tuple that is new since the last round.
+      let mut delta = Facts::default();
+      for rule in &rules {
+        for tuple in Plan::new(&rule.body).solve(facts, &derived, None, &rule.head) {
+          delta.add(&rule.head.relation, tuple);
+        }
+      }
+      loop {
+        let mut changed = false;
+        for (relation, tuples) in delta.relations() {
+          for tuple in tuples {
+            changed |= derived.add(relation, tuple.clone());
+          }
+        }
+        if !changed {
+          break;
+        }
+
+        let mut next = Facts::default();
+        for rule in &rules {
+          let plan = Plan::new(&rule.body);
+          for (i, literal) in rule.body.iter().enumerate() {
+            let Literal::Positive(atom) = literal else {
+              continue;
+            };
+            if !recursive.contains(atom.relation.as_str()) {
+              continue;
+            }
+            for tuple in plan.solve(facts, &derived, Some((i, &delta)), &rule.head) {
+              if !derived.contains(&rule.head.relation, &tuple) {
+                next.add(&rule.head.relation, tuple);
+              }
+            }
+          }
+        }
+        delta = next;
+      }
+    }
+
+    Ok(
+      self
+        .queries
+        .iter()
+        .map(|query| {
+          let columns = variables(&query.body);
+          let head = Atom {
+            relation: String::new(),
+            terms: columns.iter().map(|var| Term::Var(var.clone())).collect(),
+          };
+          let rows = Plan::new(&query.body)
+            .solve(facts, &derived, None, &head)
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+          QueryResult {
+            query: query.text.clone(),
+            columns,
+            rows,
+          }
+        })
+        .collect(),
+    )
+  }
+
+  /// Checks that every relation is used with a consistent arity, that every rule is safe,
+  /// and that negation is stratified. Returns the indices of the rules in each stratum.
+  fn check(&self) -> Result<Vec<Vec<usize>>> {
+    let mut arities = RELATIONS
+      .iter()
+      .map(|(relation, columns)| (relation.to_string(), columns.len()))
+      .collect::<HashMap<_, _>>();
+    for rule in &self.rules {
+      ensure!(
+        Builtin::from_name(&rule.head.relation).is_none(),
+        "line {}: cannot define rules for the string predicate `{}`",
+        rule.line,
+        rule.head.relation
+      );
+      ensure!(
+        !RELATIONS
+          .iter()
+          .any(|(name, _)| *name == rule.head.relation),
+        "line {}: cannot define rules for the built-in relation `{}`",
+        rule.line,
+        rule.head.relation
+      );
+      let arity = arities
+        .entry(rule.head.relation.clone())
+        .or_insert(rule.head.terms.len());
+      ensure!(
+        *arity == rule.head.terms.len(),
+        "line {}: `{}` is used with {} arguments, but elsewhere with {arity}",
+        rule.line,
+        rule.head.relation,
+        rule.head.terms.len()
+      );
+    }
+
+    let bodies = self
+      .rules
+      .iter()
+      .map(|rule| (&rule.body, rule.line))
+      .chain(self.queries.iter().map(|query| (&query.body, query.line)));
+    for (body, line) in bodies {
+      for literal in body {
+        let (Literal::Positive(atom) | Literal::Negative(atom)) = literal else {
+          continue;
+        };
+        let arity = arities.get(&atom.relation).with_context(|| {
+          format!("line {line}: unknown relation `{}`", atom.relation)
+        })?;
+        ensure!(
+          *arity == atom.terms.len(),
+          "line {line}: `{}` takes {arity} arguments, but was given {}",
+          atom.relation,
+          atom.terms.len()
+        );
+      }
+
+      let bound = body
+        .iter()
+        .filter_map(|literal| match literal {
+          Literal::Positive(atom) => Some(atom.terms.iter()),
+          _ => None,
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+      for literal in body {
+        if matches!(literal, Literal::Positive(_)) {
+          continue;
+        }
+        for term in literal.terms() {
+          ensure!(
+            !(matches!(literal, Literal::Builtin(..)) && *term == Term::Wildcard),
+            "line {line}: `_` cannot be an argument of a string predicate"
+          );
+          if let Term::Var(var) = term {
+            ensure!(
+              bound.contains(&term),
+              "line {line}: variable `{var}` must appear in a positive atom"
+            );
+          }
+        }
+      }
+    }
+    for rule in &self.rules {
+      for term in &rule.head.terms {
+        match term {
+          Term::Var(var) => ensure!(
+            rule.body.iter().any(|literal| matches!(
+              literal, Literal::Positive(atom) if atom.terms.contains(term)
+            )),
+            "line {}: variable `{var}` in the head must appear in a positive atom",
+            rule.line
+          ),
+          Term::Wildcard => {
+            bail!("line {}: `_` cannot appear in a rule's head", rule.line)
+          }
+          Term::Const(_) => {}
+        }
+      }
+    }
+
+    // Assign each derived relation the lowest stratum that is above its negated dependencies.
+    let mut strata = self
+      .rules
+      .iter()
+      .map(|rule| (rule.head.relation.as_str(), 0))
+      .collect::<HashMap<_, _>>();
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for rule in &self.rules {
+        let mut stratum = strata[rule.head.relation.as_str()];
+        for literal in &rule.body {
+          match literal {
+            Literal::Positive(atom) => {
+              stratum =
+                stratum.max(strata.get(atom.relation.as_str()).copied().unwrap_or(0))
+            }
+            Literal::Negative(atom) => {
+              stratum = stratum.max(
+                strata
+                  .get(atom.relation.as_str())
+                  .map_or(0, |stratum| stratum + 1),
+              )
+            }
+            Literal::Builtin(..) => {}
+          }
+        }
+        if stratum > strata[rule.head.relation.as_str()] {
+          ensure!(
+            stratum <= strata.len(),
+            "line {}: `{}` depends on its own negation",
+            rule.line,
+            rule.head.relation
+          );
+          strata.insert(&rule.head.relation, stratum);
+          changed = true;
+        }
+      }
+    }
+
+    let count = strata.values().max().map_or(0, |max| max + 1);
+    let mut rules = vec![Vec::new(); count];
+    for (i, rule) in self.rules.iter().enumerate() {
+      rules[strata[rule.head.relation.as_str()]].push(i);
+    }
+    Ok(rules)
+  }
+}
+
+/// Returns the named variables of a body in order of first appearance.
+fn variables(body: &[Literal]) -> Vec<String> {
+  let mut variables = Vec::new();
+  for literal in body {
+    for term in literal.terms() {
+      if let Term::Var(var) = term
+        && !variables.contains(var)
+      {
+        variables.push(var.clone());
+      }
+    }
+  }
+  variables
+}
+
+/// An order in which to evaluate the literals of a body, where each negation
+/// and comparison is checked as soon as all of its variables are bound.
+struct Plan<'a> {
+  steps: Vec<(usize, &'a Literal)>,
+}
+
+type Bindings = HashMap<String, String>;
+
+impl<'a> Plan<'a> {
+  fn new(body: &'a [Literal]) -> Self {
+    let mut steps = Vec::new();
+    let mut bound = HashSet::new();
+    let mut pending = body
+      .iter()
+      .enumerate()
+      .filter(|(_, literal)| !matches!(literal, Literal::Positive(_)))
+      .collect::<Vec<_>>();
+    let is_bound = |literal: &Literal, bound: &HashSet<&Term>| {
+      literal
+        .terms()
+        .iter()
+        .all(|term| !matches!(term, Term::Var(_)) || bound.contains(term))
+    };
+    for (i, literal) in body.iter().enumerate() {
+      if let Literal::Positive(atom) = literal {
+        steps.push((i, literal));
+        bound.extend(atom.terms.iter());
+      }
+      let (ready, rest) = pending
+        .into_iter()
+        .partition::<Vec<_>, _>(|(_, literal)| is_bound(literal, &bound));
+      steps.extend(ready);
+      pending = rest;
+    }
+    Plan { steps }
+  }
+
+  /// Returns the instances of `head` for every solution to the body. If `delta` is `Some((i, delta))`,
+  /// then the `i`-th literal of the body is matched against `delta` instead of all tuples.
+  fn solve(
+    &self,
+    facts: &Facts,
+    derived: &Facts,
+    delta: Option<(usize, &Facts)>,
+    head: &Atom,
+  ) -> Vec<Vec<String>> {
+    let mut solutions = vec![Bindings::new()];
+    for (i, literal) in &self.steps {
+      solutions = match literal {
+        Literal::Positive(atom) => {
+          let tuples = match delta {
+            Some((j, delta)) if j == *i => delta.get(&atom.relation).collect::<Vec<_>>(),
+            _ => lookup(facts, derived, &atom.relation).collect(),
+          };
+          join(solutions, atom, &tuples)
+        }
+        Literal::Negative(atom) => solutions
+          .into_iter()
+          .filter(|bindings| {
+            if atom.terms.contains(&Term::Wildcard) {
+              !lookup(facts, derived, &atom.relation)
+                .any(|tuple| matches(atom, tuple, bindings).is_some())
+            } else {
+              let tuple = atom
+                .terms
+                .iter()
+                .map(|term| value(term, bindings).to_string())
+                .collect::<Vec<_>>();
+              !facts.contains(&atom.relation, &tuple)
+                && !derived.contains(&atom.relation, &tuple)
+            }
+          })
+          .collect(),
+        Literal::Builtin(builtin, lhs, rhs) => solutions
+          .into_iter()
+          .filter(|bindings| builtin.holds(value(lhs, bindings), value(rhs, bindings)))
+          .collect(),
+      };
+    }
+    solutions
+      .iter()
+      .map(|bindings| {
+        head
+          .terms
+          .iter()
+          .map(|term| value(term, bindings).to_string())
+          .collect()
+      })
+      .collect()
+  }
+}
+
+fn lookup<'a>(
+  facts: &'a Facts,
+  derived: &'a Facts,
+  relation: &'a str,
+) -> impl Iterator<Item = &'a Vec<String>> + 'a {
+  facts.get(relation).chain(derived.get(relation))
+}
+
+fn value<'a>(term: &'a Term, bindings: &'a Bindings) -> &'a str {
+  match term {
+    Term::Var(var) => &bindings[var],
+    Term::Const(value) => value,
+    Term::Wildcard => unreachable!("wildcards are never evaluated"),
+  }
+}
+
+/// Extends `bindings` so that `atom` matches `tuple`, if possible.
+fn matches(atom: &Atom, tuple: &[String], bindings: &Bindings) -> Option<Bindings> {
+  let mut bindings = bindings.clone();
+  for (term, value) in atom.terms.iter().zip(tuple) {
+    match term {
+      Term::Wildcard => {}
+      Term::Const(constant) => {
+        if constant != value {
+          return None;
+        }
+      }
+      Term::Var(var) => match bindings.get(var) {
+        Some(bound) if bound != value => return None,
+        Some(_) => {}
+        None => {
+          bindings.insert(var.clone(), value.clone());
+        }
+      },
+    }
+  }
+  Some(bindings)
+}
+
+/// Joins each of `solutions` with the tuples that match `atom`, using a hash index
+/// on the columns of `atom` that are bound in every solution.
+fn join(solutions: Vec<Bindings>, atom: &Atom, tuples: &[&Vec<String>]) -> Vec<Bindings> {
+  let Some(first) = solutions.first() else {
+    return solutions;
+  };
+  let key_columns = atom
+    .terms
+    .iter()
+    .enumerate()
+    .filter(|(_, term)| match term {
+      Term::Const(_) => true,
+      Term::Var(var) => first.contains_key(var),
+      Term::Wildcard => false,
+    })
+    .map(|(i, _)| i)
+    .collect::<Vec<_>>();
+
+  let mut index: HashMap<Vec<&str>, Vec<&Vec<String>>> = HashMap::new();
+  for tuple in tuples {
+    let key = key_columns.iter().map(|i| tuple[*i].as_str()).collect();
+    index.entry(key).or_default().push(tuple);
+  }
+
+  solutions
+    .iter()
+    .flat_map(|bindings| {
+      let key = key_columns
+        .iter()
+        .map(|i| value(&atom.terms[*i], bindings))
+        .collect::<Vec<_>>();
+      index
+        .get(&key)
+        .into_iter()
+        .flatten()
+        .filter_map(|tuple| matches(atom, tuple, bindings))
+        .collect::<Vec<_>>()
+    })
+    .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+  Ident(String),
+  Var(String),
+  Wildcard,
+  Const(String),
+  LParen,
+  RParen,
+  Comma,
+  Dot,
+  If,
+  Query,
+  Not,
+  Eq,
+  Neq,
+}
+
+impl fmt::Display for Token {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Token::Ident(s) | Token::Var(s) => write!(f, "`{s}`"),
+      Token::Const(s) => write!(f, "{s:?}"),
+      Token::Wildcard => write!(f, "`_`"),
+      Token::LParen => write!(f, "`(`"),
+      Token::RParen => write!(f, "`)`"),
+      Token::Comma => write!(f, "`,`"),
+      Token::Dot => write!(f, "`.`"),
+      Token::If => write!(f, "`:-`"),
+      Token::Query => write!(f, "`?-`"),
+      Token::Not => write!(f, "`!`"),
+      Token::Eq => write!(f, "`=`"),
+      Token::Neq => write!(f, "`!=`"),
+    }
+  }
+}
+
+/// Splits a program into tokens, each with its line number and byte offset.
+fn tokenize(input: &str) -> Result<Vec<(Token, usize, usize)>> {
+  let mut tokens = Vec::new();
+  let mut chars = input.char_indices().peekable();
+  let mut line = 1;
+  while let Some((start, c)) = chars.next() {
+    let next = chars.peek().map(|(_, c)| *c);
+    let token = match c {
+      '\n' => {
+        line += 1;
+        continue;
+      }
+      c if c.is_whitespace() => continue,
+      '%' => {
+        while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+        continue;
+      }
+      '/' if next == Some('/') => {
+        while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+        continue;
+      }
+      '(' => Token::LParen,
+      ')' => Token::RParen,
+      ',' => Token::Comma,
+      '.' => Token::Dot,
+      '=' => Token::Eq,
+      ':' if next == Some('-') => {
+        chars.next();
+        Token::If
+      }
+      '?' if next == Some('-') => {
+        chars.next();
+        Token::Query
+      }
+      '!' if next == Some('=') => {
+        chars.next();
+        Token::Neq
+      }
+      '!' => Token::Not,
+      '"' => {
+        let mut value = String::new();
+        loop {
+          match chars.next() {
+            Some((_, '"')) => break,
+            Some((_, '\\')) => match chars.next() {
+              Some((_, 'n')) => value.push('\n'),
+              Some((_, c)) => value.push(c),
+              None => bail!("line {line}: unterminated string"),
+            },
+            Some((_, c)) => {
+              if c == '\n' {
+                line += 1;
+              }
+              value.push(c);
+            }
+            None => bail!("line {line}: unterminated string"),
+          }
+        }
+        Token::Const(value)
+      }
+      c if c.is_alphanumeric() || c == '_' => {
+        let mut word = String::from(c);
+        while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_')
+        {
+          word.push(c);
+        }
+        if word == "_" {
+          Token::Wildcard
+        } else if word == "not" {
+          Token::Not
+        } else if c.is_ascii_digit() {
+          Token::Const(word)
+        } else if c.is_uppercase() || c == '_' {
+          Token::Var(word)
+        } else {
+          Token::Ident(word)
+        }
+      }
+      c => bail!("line {line}: unexpected character `{c}`"),
+    };
+    tokens.push((token, line, start));
+  }
+  Ok(tokens)
+}
+
+struct Parser<'a> {
+  input: &'a str,
+  tokens: Vec<(Token, usize, usize)>,
+  pos: usize,
+}
+
+impl Parser<'_> {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.pos).map(|(token, _, _)| token)
+  }
+
+  fn line(&self) -> usize {
+    self
+      .tokens
+      .get(self.pos)
+      .or(self.tokens.last())
+      .map_or(1, |(_, line, _)| *line)
+  }
+
+  fn next(&mut self) -> Result<Token> {
+    let line = self.line();
+    let (token, _, _) = self
+      .tokens
+      .get(self.pos)
+      .cloned()
+      .with_context(|| format!("line {line}: unexpected end of program"))?;
+    self.pos += 1;
+    Ok(token)
+  }
+
+  fn expect(&mut self, expected: Token) -> Result<()> {
+    let line = self.line();
+    let token = self.next()?;
+    ensure!(
+      token == expected,
+      "line {line}: expected {expected}, found {token}"
+    );
+    Ok(())
+  }
+
+  fn term(&mut self) -> Result<Term> {
+    let line = self.line();
+    Ok(match self.next()? {
+      Token::Var(var) => Term::Var(var),
+      Token::Const(value) => Term::Const(value),
+      Token::Wildcard => Term::Wildcard,
+      token => bail!("line {line}: expected a variable or constant, found {token}"),
+    })
+  }
+
+  fn atom(&mut self) -> Result<Atom> {
+    let line = self.line();
+    let relation = match self.next()? {
+      Token::Ident(relation) => relation,
+      token => bail!("line {line}: expected a relation, found {token}"),
+    };
+    self.expect(Token::LParen)?;
+    let mut terms = vec![self.term()?];
+    while self.peek() == Some(&Token::Comma) {
+      self.pos += 1;
+      terms.push(self.term()?);
+    }
+    self.expect(Token::RParen)?;
+    Ok(Atom { relation, terms })
+  }
+
+  fn literal(&mut self) -> Result<Literal> {
+    let line = self.line();
+    match self.peek() {
+      Some(Token::Not) => {
+        self.pos += 1;
+        Ok(Literal::Negative(self.atom()?))
+      }
+      Some(Token::Ident(_)) => {
+        let atom = self.atom()?;
+        match Builtin::from_name(&atom.relation) {
+          Some(builtin) => {
+            let [lhs, rhs] = <[Term; 2]>::try_from(atom.terms).map_err(|_| {
+              anyhow::anyhow!("line {line}: `{}` takes 2 arguments", atom.relation)
+            })?;
+            Ok(Literal::Builtin(builtin, lhs, rhs))
+          }
+          None => Ok(Literal::Positive(atom)),
+        }
+      }
+      _ => {
+        let lhs = self.term()?;
+        let builtin = match self.next()? {
+          Token::Eq => Builtin::Eq,
+          Token::Neq => Builtin::Neq,
+          token => bail!("line {line}: expected `=` or `!=`, found {token}"),
+        };
+        let rhs = self.term()?;
+        ensure!(
+          lhs != Term::Wildcard && rhs != Term::Wildcard,
+          "line {line}: `_` cannot be compared"
+        );
+        Ok(Literal::Builtin(builtin, lhs, rhs))
+      }
+    }
+  }
+
+  fn body(&mut self) -> Result<Vec<Literal>> {
+    let mut body = vec![self.literal()?];
+    while self.peek() == Some(&Token::Comma) {
+      self.pos += 1;
+      body.push(self.literal()?);
+    }
+    self.expect(Token::Dot)?;
+    Ok(body)
+  }
+
+  fn program(&mut self) -> Result<Program> {
+    let mut program = Program {
+      rules: Vec::new(),
+      queries: Vec::new(),
+    };
+    while self.peek().is_some() {
+      let line = self.line();
+      if self.peek() == Some(&Token::Query) {
+        self.pos += 1;
+        let start = self.tokens[self.pos.min(self.tokens.len() - 1)].2;
+        let body = self.body()?;
+        let end = self.tokens[self.pos - 1].2;
+        let text = self.input[start .. end]
+          .split_whitespace()
+          .collect::<Vec<_>>();
+        program.queries.push(Query {
+          text: text.join(" "),
+          body,
+          line,
+        });
+      } else {
+        let head = self.atom()?;
+        let body = match self.next()? {
+          Token::Dot => Vec::new(),
+          Token::If => self.body()?,
+          token => bail!("line {line}: expected `:-` or `.`, found {token}"),
+        };
+        program.rules.push(Rule { head, body, line });
+      }
+    }
+    Ok(program)
+  }
+}
+
+impl FromStr for Program {
+  type Err = anyhow::Error;
+
+  fn from_str(input: &str) -> Result<Self> {
+    Parser {
+      input,
+      tokens: tokenize(input)?,
+      pos: 0,
+    }
+    .program()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn facts() -> Facts {
+    let mut facts = Facts::default();
+    for (relation, tuple) in [
+      ("arg", vec!["main", "_1", "password", "Password"]),
+      ("arg", vec!["main", "_2", "name", "String"]),
+      ("call", vec!["main", "bb1[0]", "log::info"]),
+      ("call", vec!["main", "bb2[0]", "hash"]),
+      ("call", vec!["main", "bb3[0]", "log::info"]),
+      ("flows", vec!["main", "_1", "bb1[0]"]),
+      ("flows", vec!["main", "_2", "bb3[0]"]),
+      ("edge", vec!["main", "_1", "bb2[0]", "data"]),
+      ("edge", vec!["main", "bb2[0]", "bb3[0]", "data"]),
+      ("edge", vec!["main", "bb3[0]", "bb4[0]", "data"]),
+    ] {
+      facts.add(relation, tuple.into_iter().map(String::from).collect());
+    }
+    facts
+  }
+
+  fn evaluate(program: &str) -> Result<Vec<Vec<Vec<String>>>> {
+    let program = program.parse::<Program>()?;
+    let results = program.evaluate(&facts())?;
+    Ok(results.into_iter().map(|result| result.rows).collect())
+  }
+
+  fn rows(rows: &[&[&str]]) -> Vec<Vec<String>> {
+    rows
+      .iter()
+      .map(|row| row.iter().map(|s| s.to_string()).collect())
+      .collect()
+  }
+
+  #[test]
+  fn test_datalog_query() {
+    let results = evaluate(
+      r#"
+% Does an argument of type Password reach a call to log::info?
+?- arg(F, A, _, T), contains(T, "Password"), flows(F, A, L), call(F, L, "log::info").
+"#,
+    )
+    .unwrap();
+    assert_eq!(results, vec![rows(&[&[
+      "main", "_1", "Password", "bb1[0]"
+    ]])]);
+  }
+
+  #[test]
+  fn test_datalog_recursion_and_negation() {
+    let results = evaluate(
+      r#"
+reach(F, X, Y) :- edge(F, X, Y, _).
+reach(F, X, Z) :- reach(F, X, Y), edge(F, Y, Z, _).
+sanitized(F, L) :- call(F, L, "hash").
+unsanitized(F, X, Y) :- reach(F, X, Y), !sanitized(F, Y).
+?- reach("main", "_1", Y).
+?- unsanitized(F, "_1", Y), Y != "bb4[0]".
+"#,
+    )
+    .unwrap();
+    assert_eq!(results, vec![
+      rows(&[&["bb2[0]"], &["bb3[0]"], &["bb4[0]"]]),
+      rows(&[&["main", "bb3[0]"]]),
+    ]);
+  }
+
+  #[test]
+  fn test_datalog_errors() {
+    let error = |program: &str| evaluate(program).unwrap_err().to_string();
+    assert!(error("?- nope(X).").contains("unknown relation `nope`"));
+    assert!(error("?- call(F, L).").contains("takes 3 arguments"));
+    assert!(error("p(X) :- call(F, L, C).").contains("variable `X`"));
+    assert!(error("p(F) :- function(F), !p(F).").contains("its own negation"));
+    assert!(error("?- call(F, L, C)").contains("unexpected end"));
+    assert!(error("call(\"a\", \"b\", \"c\").").contains("built-in relation"));
+  }
+}
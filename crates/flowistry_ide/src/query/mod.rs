@@ -0,0 +1,76 @@
+//! The `query` command, which answers Datalog queries over the flow facts of a crate.
+
+use anyhow::Result;
+use flowistry::{
+  extensions::{EVAL_MODE, EvalMode},
+  infoflow::{
+    self,
+    facts::Facts,
+    summaries::{FLOW_SUMMARIES, FlowSummaries},
+  },
+};
+use fluid_let::fluid_set;
+use log::debug;
+use rustc_middle::ty::TyCtxt;
+use rustc_utils::{
+  mir::borrowck_facts::{self, get_body_with_borrowck_facts},
+  source_map::find_bodies::find_bodies,
+};
+
+pub use self::datalog::{Program, QueryResult};
+use crate::plugin::{FlowistryError, FlowistryResult};
+
+mod datalog;
+
+struct Callbacks {
+  program: Program,
+  output: Option<Result<Vec<QueryResult>>>,
+  eval_mode: Option<EvalMode>,
+  summaries: Option<FlowSummaries>,
+}
+
+impl rustc_driver::Callbacks for Callbacks {
+  fn config(&mut self, config: &mut rustc_interface::Config) {
+    borrowck_facts::enable_mir_simplification();
+    config.override_queries = Some(borrowck_facts::override_queries);
+  }
+
+  fn after_analysis<'tcx>(
+    &mut self,
+    _compiler: &rustc_interface::interface::Compiler,
+    tcx: TyCtxt<'tcx>,
+  ) -> rustc_driver::Compilation {
+    fluid_set!(EVAL_MODE, self.eval_mode.unwrap_or_default());
+    fluid_set!(FLOW_SUMMARIES, self.summaries.take().unwrap_or_default());
+
+    let mut facts = Facts::default();
+    for (_, body_id) in find_bodies(tcx) {
+      let def_id = tcx.hir_body_owner_def_id(body_id);
+      debug!("Exporting facts for {}", tcx.def_path_str(def_id));
+      let body_with_facts = get_body_with_borrowck_facts(tcx, def_id);
+      let results = infoflow::compute_flow(tcx, body_id, body_with_facts);
+      facts.add_body(&results);
+    }
+    self.output = Some(self.program.evaluate(&facts));
+    FLOW_SUMMARIES.get(|summaries| summaries.map(FlowSummaries::warn_unused));
+
+    rustc_driver::Compilation::Stop
+  }
+}
+
+/// Evaluates `program` over the facts of every body in the crate.
+pub fn query(args: &[String], program: Program) -> FlowistryResult<Vec<QueryResult>> {
+  let mut callbacks = Callbacks {
+    program,
+    output: None,
+    eval_mode: EVAL_MODE.copied(),
+    summaries: FLOW_SUMMARIES.get(|summaries| summaries.cloned()),
+  };
+  crate::plugin::run_with_callbacks(args, &mut callbacks)?;
+  callbacks
+    .output
+    .unwrap()
+    .map_err(|e| FlowistryError::AnalysisError {
+      error: e.to_string(),
+    })
+}
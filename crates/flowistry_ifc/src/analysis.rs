@@ -1,47 +1,133 @@
 #![allow(dead_code)]
 
-use std::io::Write;
+use std::{cell::OnceCell, iter};
 
-use anyhow::Result;
-use flowistry::{infoflow::FlowResults, mir::utils::PlaceSet};
-use rustc_data_structures::fx::FxHashMap as HashMap;
-use rustc_hir::{BodyId, def::Res, def_id::DefId};
+use either::Either;
+use flowistry::{
+  extensions::{ControlMode, EVAL_MODE, EvalMode},
+  infoflow::{
+    self, FlowDomain, FlowResults,
+    summaries::{FLOW_SUMMARIES, FlowSummaries, FunctionSummary},
+  },
+  mir::utils::PlaceSet,
+};
+use fluid_let::fluid_set;
+use glob::Pattern;
+use rustc_abi::{FIRST_VARIANT, FieldIdx};
+use rustc_data_structures::fx::{
+  FxHashMap as HashMap, FxHashSet as HashSet, FxIndexMap, FxIndexSet,
+};
+use rustc_hir::{
+  BodyId, HirId,
+  def::Res,
+  def_id::{DefId, LOCAL_CRATE},
+};
 use rustc_infer::traits::EvaluationResult;
+use rustc_lint_defs::{Level, Lint, declare_lint};
 use rustc_middle::{
-  mir::*,
-  ty::{ParamEnv, Ty, TyCtxt, TypingMode},
+  mir::{
+    visit::{PlaceContext, Visitor},
+    *,
+  },
+  ty::{Instance, ParamEnv, Ty, TyCtxt, TyKind, TypingEnv, TypingMode},
 };
-use rustc_mir_dataflow::JoinSemiLattice;
-use rustc_span::FileName;
+use rustc_span::{Span, Symbol};
 use rustc_trait_selection::infer::{InferCtxtExt, TyCtxtInferExt};
-use rustc_utils::{BodyExt, PlaceExt, SpanExt};
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use rustc_utils::{
+  BodyExt, OperandExt, PlaceExt, SpanExt,
+  mir::{
+    borrowck_facts,
+    location_or_arg::{LocationOrArg, index::LocationOrArgSet},
+  },
+  source_map::find_bodies::find_bodies,
+};
 
-fn implements_trait<'tcx>(
+use crate::{
+  class::{self, Branches},
+  config::IfcConfig,
+  label::{Label, Lattice},
+  report::{self, Finding, FlowClass, SourceRange},
+};
+
+/// Returns true if `ty` implements the trait with the given generic arguments (after `Self`).
+pub(crate) fn implements_trait<'tcx>(
   tcx: TyCtxt<'tcx>,
   param_env: ParamEnv<'tcx>,
   ty: Ty<'tcx>,
   trait_def_id: DefId,
+  trait_args: &[Ty<'tcx>],
 ) -> bool {
   let infcx = tcx.infer_ctxt().build(TypingMode::non_body_analysis());
   let ty = tcx.erase_regions(ty);
-  let result = infcx.type_implements_trait(trait_def_id, [ty], param_env);
+  let params = iter::once(ty).chain(trait_args.iter().copied());
+  let result = infcx.type_implements_trait(trait_def_id, params, param_env);
   matches!(
     result,
     EvaluationResult::EvaluatedToOk | EvaluationResult::EvaluatedToOkModuloRegions
   )
 }
 
-pub enum IssueFound {
-  Yes,
-  No,
+/// Returns `ty` and the types of the data that a value of type `ty` holds or points to
+/// without a field of its own, e.g. the `T` in `&mut T`, `Box<T>`, `Vec<T>` or `[T; N]`.
+/// A generic argument of an ADT is assumed to be data of the ADT, so `Option<T>` also holds a `T`.
+pub(crate) fn contained_types(ty: Ty<'_>) -> Vec<Ty<'_>> {
+  let mut types = Vec::new();
+  let mut queue = vec![ty];
+  while let Some(ty) = queue.pop() {
+    if types.contains(&ty) {
+      continue;
+    }
+    types.push(ty);
+    match ty.kind() {
+      TyKind::Ref(_, ty, _)
+      | TyKind::RawPtr(ty, _)
+      | TyKind::Array(ty, _)
+      | TyKind::Slice(ty) => queue.push(*ty),
+      TyKind::Tuple(tys) => queue.extend(tys.iter()),
+      TyKind::Adt(_, args) => queue.extend(args.types()),
+      _ => {}
+    }
+  }
+  types
+}
+
+/// Returns the functions called by `func`: the called item itself, and the implementation
+/// it resolves to if that is different (e.g. for a trait method).
+pub(crate) fn callees<'tcx>(
+  tcx: TyCtxt<'tcx>,
+  typing_env: TypingEnv<'tcx>,
+  func: &Operand<'tcx>,
+) -> Vec<DefId> {
+  let Some(constant) = func.constant() else {
+    return Vec::new();
+  };
+  let TyKind::FnDef(def_id, args) = *constant.const_.ty().kind() else {
+    return Vec::new();
+  };
+  let mut callees = vec![def_id];
+  let args = tcx.erase_regions(args);
+  if let Ok(Some(instance)) = Instance::try_resolve(tcx, typing_env, def_id, args)
+    && instance.def_id() != def_id
+  {
+    callees.push(instance.def_id());
+  }
+  callees
 }
 
-pub fn analyze(body_id: &BodyId, results: &FlowResults) -> Result<IssueFound> {
-  let tcx = results.analysis.tcx;
-  let body = results.analysis.body;
-  let def_id = tcx.hir_body_owner_def_id(*body_id).to_def_id();
+/// Returns the paths of the functions called by `func`, see [`callees`].
+pub(crate) fn callee_paths<'tcx>(
+  tcx: TyCtxt<'tcx>,
+  typing_env: TypingEnv<'tcx>,
+  func: &Operand<'tcx>,
+) -> Vec<String> {
+  callees(tcx, typing_env, func)
+    .into_iter()
+    .map(|def_id| tcx.def_path_str(def_id))
+    .collect()
+}
 
+/// Returns the items exported by `flowistry_ifc_traits` by name, if the crate is a dependency.
+pub(crate) fn ifc_items(tcx: TyCtxt) -> HashMap<String, DefId> {
   log::debug!(
     "Crates: {:?}",
     tcx
@@ -50,148 +136,1138 @@ pub fn analyze(body_id: &BodyId, results: &FlowResults) -> Result<IssueFound> {
       .map(|krate| tcx.crate_name(*krate))
       .collect::<Vec<_>>()
   );
-  let ifc_crate = match tcx
+  let ifc_crate = tcx
     .crates(())
     .iter()
     .find(|krate| tcx.crate_name(**krate).as_str() == "flowistry_ifc_traits")
-  {
-    Some(c) => *c,
-    None => {
-      return Ok(IssueFound::No);
+    .copied();
+  match ifc_crate {
+    Some(ifc_crate) => {
+      let ifc_mod = DefId {
+        krate: ifc_crate,
+        index: rustc_hir::def_id::CRATE_DEF_INDEX,
+      };
+      tcx
+        .module_children(ifc_mod)
+        .iter()
+        .filter_map(|export| match export.res {
+          Res::Def(_, id) => Some((export.ident.to_string(), id)),
+          _ => None,
+        })
+        .collect::<HashMap<_, _>>()
     }
-  };
+    None => HashMap::default(),
+  }
+}
+
+/// Returns true if the item (a function or a field) is annotated with `#[ifc::secret]`.
+///
+/// The macros in `flowistry_ifc_traits` expand `#[ifc::secret]` to
+/// `#[cfg_attr(flowistry_ifc, flowistry_ifc::secret)]`, which is a tool attribute
+/// when `cargo ifc` checks the crate.
+pub(crate) fn is_secret(tcx: TyCtxt, def_id: DefId) -> bool {
+  tcx.has_attrs_with_path(def_id, &secret_attr())
+}
+
+fn secret_attr() -> [Symbol; 2] {
+  [Symbol::intern("flowistry_ifc"), Symbol::intern("secret")]
+}
+
+/// Returns flow summaries that cut the flow from the arguments to the return value of each
+/// declassifying function called in `body`, i.e. `declassify`, `sanitize`, and functions that
+/// return a type implementing `Declassified`.
+pub(crate) fn declassifier_summaries<'tcx>(
+  tcx: TyCtxt<'tcx>,
+  body: &Body<'tcx>,
+  def_id: DefId,
+) -> FlowSummaries {
+  let ifc_items = ifc_items(tcx);
+  let mut summaries = FlowSummaries::default();
+  for name in ["declassify", "sanitize"] {
+    if let Some(def_id) = ifc_items.get(name) {
+      summaries.insert(tcx.def_path_str(*def_id), FunctionSummary::default());
+    }
+  }
 
-  let ifc_mod = DefId {
-    krate: ifc_crate,
-    index: rustc_hir::def_id::CRATE_DEF_INDEX,
+  let Some(declassified) = ifc_items.get("Declassified") else {
+    return summaries;
   };
-  let ifc_items = tcx
-    .module_children(ifc_mod)
-    .iter()
-    .filter_map(|export| match export.res {
-      Res::Def(_, id) => Some((export.ident.to_string(), id)),
-      _ => None,
-    })
-    .collect::<HashMap<_, _>>();
-
-  let all_places = body
-    .local_decls()
-    .indices()
-    .flat_map(|local| {
-      let place = Place::from_local(local, tcx);
-      place.interior_places(tcx, body, def_id)
-    })
-    .collect::<PlaceSet>();
+  let typing_env = TypingEnv::post_analysis(tcx, def_id);
+  for data in body.basic_blocks.iter() {
+    let TerminatorKind::Call {
+      func, destination, ..
+    } = &data.terminator().kind
+    else {
+      continue;
+    };
+    let ty = destination.ty(body.local_decls(), tcx).ty;
+    if implements_trait(tcx, tcx.param_env(def_id), ty, *declassified, &[]) {
+      for path in callee_paths(tcx, typing_env, func) {
+        summaries.insert(path, FunctionSummary::default());
+      }
+    }
+  }
+  summaries
+}
 
-  let find_implements = |trait_def_id| -> PlaceSet {
-    all_places
-      .iter()
-      .copied()
-      .filter(|place| {
-        let ty = place.ty(body.local_decls(), tcx).ty;
-        implements_trait(tcx, tcx.param_env(def_id), ty, trait_def_id)
+/// Collects the places that are read in a body, along with the locations that read them.
+struct PlaceReads<'tcx> {
+  reads: Vec<(Place<'tcx>, Location)>,
+}
+
+impl<'tcx> Visitor<'tcx> for PlaceReads<'tcx> {
+  fn visit_place(
+    &mut self,
+    place: &Place<'tcx>,
+    context: PlaceContext,
+    location: Location,
+  ) {
+    if context.is_use() && (!context.is_mutating_use() || context.is_borrow()) {
+      self.reads.push((*place, location));
+    }
+  }
+}
+
+/// Collects the locals that index into arrays and slices, along with their locations.
+struct IndexLocals {
+  indices: Vec<(Local, Location)>,
+}
+
+impl<'tcx> Visitor<'tcx> for IndexLocals {
+  fn visit_projection_elem(
+    &mut self,
+    place_ref: PlaceRef<'tcx>,
+    elem: PlaceElem<'tcx>,
+    context: PlaceContext,
+    location: Location,
+  ) {
+    if let ProjectionElem::Index(local) = elem {
+      self.indices.push((local, location));
+    }
+    self.super_projection_elem(place_ref, elem, context, location);
+  }
+}
+
+declare_lint! {
+  /// The `insecure_flow` lint detects data that flows from a source into a sink that must not
+  /// receive it, e.g. a `Secure` value that is printed with `insecure_print!`.
+  pub INSECURE_FLOW,
+  Deny,
+  "data flows from a source into a sink that must not receive it"
+}
+
+declare_lint! {
+  /// The `implicit_flow` lint detects sinks that are reached depending on a source,
+  /// e.g. a print inside of `if secret { ... }`.
+  pub IMPLICIT_FLOW,
+  Warn,
+  "a sink is reached depending on a source that it must not receive"
+}
+
+declare_lint! {
+  /// The `termination_flow` lint detects sinks that are reached after a loop or an early return
+  /// whose condition depends on a source.
+  pub TERMINATION_FLOW,
+  Warn,
+  "whether a sink is reached depends on the termination of a branch on a source"
+}
+
+declare_lint! {
+  /// The `timing_flow` lint detects branch conditions and array indices that depend on a source,
+  /// which make the running time or the memory accesses of constant-time code depend on secrets.
+  pub TIMING_FLOW,
+  Allow,
+  "a branch condition or an array index depends on a source"
+}
+
+/// The lint that reports each class of flow.
+pub(crate) fn class_lint(class: FlowClass) -> &'static Lint {
+  match class {
+    FlowClass::Explicit => INSECURE_FLOW,
+    FlowClass::Implicit => IMPLICIT_FLOW,
+    FlowClass::Termination => TERMINATION_FLOW,
+    FlowClass::Timing => TIMING_FLOW,
+  }
+}
+
+/// The data of a source.
+enum SourceData<'tcx> {
+  /// The value of a place, whose dependencies depend on where the source is checked.
+  Place(Place<'tcx>),
+
+  /// Data with fixed dependencies, e.g. a parameter or the result of a call.
+  Deps(LocationOrArgSet),
+}
+
+/// Labeled data.
+struct Source<'tcx> {
+  span: Span,
+  label: Label,
+  data: SourceData<'tcx>,
+
+  /// If the data comes from a call, the spans in the callees that lead to the original source.
+  path: Vec<Span>,
+
+  /// True if the data is a secret field. Callees that receive the enclosing value check the
+  /// reads of the field themselves, so the field is not a source for their parameters.
+  field: bool,
+}
+
+impl<'tcx> Source<'tcx> {
+  /// Returns the dependencies that identify the source's data in `state`.
+  fn deps(
+    &self,
+    results: &FlowResults<'_, 'tcx>,
+    state: &FlowDomain<'tcx>,
+  ) -> LocationOrArgSet {
+    match &self.data {
+      SourceData::Place(place) => results.analysis.deps_for(state, *place),
+      SourceData::Deps(deps) => deps.clone(),
+    }
+  }
+}
+
+/// What a sink does with its data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SinkKind {
+  /// The data is written to the sink's place.
+  Data,
+
+  /// The data is the condition of a branch, see [`Checker::timing_sinks`].
+  Branch,
+
+  /// The data is an index into an array or slice, see [`Checker::timing_sinks`].
+  Index,
+}
+
+/// A labeled place that must not receive data with a higher label at a given location,
+/// e.g. the argument of a call to a sink.
+struct Sink<'tcx> {
+  span: Span,
+  label: Label,
+  location: Location,
+  place: Place<'tcx>,
+  kind: SinkKind,
+
+  /// If the place is passed to a callee, the spans in the callees that lead to the original sink.
+  path: Vec<Span>,
+
+  /// How the place reaches the original sink in the callees, or `Explicit` if the sink is in
+  /// this body. Flows into the sink are at most as strong, see [`class::weakest`].
+  class: FlowClass,
+}
+
+/// How a function's parameters and return value interact with sources and sinks,
+/// as seen by its callers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IfcSummary {
+  /// For each parameter index and label, how the parameter reaches a sink with that label.
+  param_sinks: FxIndexMap<(usize, Label), ParamSink>,
+
+  /// For each label, the spans that lead from the return value to a source with that label.
+  return_sources: FxIndexMap<Label, Vec<Span>>,
+}
+
+/// How a parameter reaches a sink, see [`IfcSummary`].
+#[derive(Debug, Clone)]
+struct ParamSink {
+  /// The spans from the function's body (or its callees' bodies) that lead from the parameter
+  /// to the sink.
+  path: Vec<Span>,
+
+  /// The strongest class of the flows from the parameter to the sink, e.g. `Implicit` if the
+  /// sink is only reached in a branch on the parameter.
+  class: FlowClass,
+}
+
+impl IfcSummary {
+  /// Returns true if both summaries have the same flows, regardless of paths.
+  fn same_flows(&self, other: &IfcSummary) -> bool {
+    self.param_sinks.len() == other.param_sinks.len()
+      && self.return_sources.len() == other.return_sources.len()
+      && self.param_sinks.iter().all(|(key, param_sink)| {
+        other
+          .param_sinks
+          .get(key)
+          .is_some_and(|other| other.class == param_sink.class)
       })
-      .collect()
-  };
-  let secure_places = find_implements(ifc_items["Secure"]);
-  let insecure_places = find_implements(ifc_items["Insecure"]);
-
-  let final_state = body
-    .all_returns()
-    .map(|location| results.state_at(location).clone())
-    .reduce(|mut a, b| {
-      a.join(&b);
-      a
+      && self
+        .return_sources
+        .keys()
+        .all(|key| other.return_sources.contains_key(key))
+  }
+}
+
+/// Checks the bodies of a crate against the IFC policy.
+///
+/// Calls to other functions in the crate are checked with an [`IfcSummary`] of the callee,
+/// so a secret passed to a helper that leaks it is reported at the call site, and a helper
+/// that returns a secret is a source in its callers.
+pub(crate) struct Checker<'tcx, 'a> {
+  tcx: TyCtxt<'tcx>,
+  config: &'a IfcConfig,
+  ifc_items: HashMap<String, DefId>,
+  lattice: Lattice<'tcx>,
+  summaries: HashMap<DefId, IfcSummary>,
+}
+
+impl<'tcx, 'a> Checker<'tcx, 'a> {
+  pub(crate) fn new(tcx: TyCtxt<'tcx>, config: &'a IfcConfig) -> Self {
+    let ifc_items = ifc_items(tcx);
+    let lattice = Lattice::build(tcx, &ifc_items);
+    Checker {
+      tcx,
+      config,
+      ifc_items,
+      lattice,
+      summaries: HashMap::default(),
+    }
+  }
+
+  /// Computes the summary of every body, iterating until the summaries of (mutually)
+  /// recursive functions reach a fixpoint.
+  pub(crate) fn compute_summaries(&mut self, bodies: &[BodyFlow<'_, 'tcx>]) {
+    loop {
+      let mut changed = false;
+      for flow in bodies {
+        let def_id = self.tcx.hir_body_owner_def_id(flow.body_id).to_def_id();
+        let summary = self.summarize(flow);
+        let old_summary = self.summaries.get(&def_id);
+        if old_summary.is_none_or(|old_summary| !old_summary.same_flows(&summary)) {
+          self.summaries.insert(def_id, summary);
+          changed = true;
+        }
+      }
+      if !changed {
+        break;
+      }
+    }
+  }
+
+  fn summarize(&self, flow: &BodyFlow<'_, 'tcx>) -> IfcSummary {
+    let body_id = &flow.body_id;
+    let results = &flow.results;
+    let body = results.analysis.body;
+    let (sources, sinks) = self.policy(body_id, results);
+    let branches = Branches::new(self.tcx, *body_id, body);
+    let mut summary = IfcSummary::default();
+    let body_span = self
+      .tcx
+      .hir_span_with_body(self.tcx.hir_body_owner(*body_id));
+    let local_span = |span: Span| span.as_local(body_span).unwrap_or(span);
+
+    for sink in &sinks {
+      let sink_deps = results
+        .analysis
+        .deps_for(results.state_at(sink.location), sink.place);
+      for index in 0 .. body.arg_count {
+        let param = LocationOrArg::Arg(Local::from_usize(index + 1));
+        if !sink_deps.contains(param) {
+          continue;
+        }
+        let mut param_deps = LocationOrArgSet::new(results.analysis.location_domain());
+        param_deps.insert(param);
+        let class = flow.classify(
+          &branches,
+          |_, _| param_deps.clone(),
+          sink,
+          local_span(sink.span),
+        );
+        let param_sink = ParamSink {
+          path: iter::once(local_span(sink.span))
+            .chain(sink.path.clone())
+            .collect(),
+          class,
+        };
+        let entry = summary.param_sinks.entry((index, sink.label));
+        let old_sink = entry.or_insert_with(|| param_sink.clone());
+        if class::strength(class) > class::strength(old_sink.class) {
+          *old_sink = param_sink;
+        }
+      }
+    }
+
+    for location in body.all_returns() {
+      let state = results.state_at(location);
+      let return_deps = results.analysis.deps_for(state, Place::return_place());
+      for source in &sources {
+        let source_deps = source.deps(results, state);
+        if !source_deps.is_empty() && return_deps.is_superset(&source_deps) {
+          summary
+            .return_sources
+            .entry(source.label)
+            .or_insert_with(|| {
+              iter::once(local_span(source.span))
+                .chain(source.path.clone())
+                .collect()
+            });
+        }
+      }
+    }
+
+    summary
+  }
+
+  /// Returns true if the field of `base` is a configured source or annotated with
+  /// `#[ifc::secret]`.
+  fn is_secret_field(
+    &self,
+    body: &Body<'tcx>,
+    base: PlaceRef<'tcx>,
+    field: FieldIdx,
+  ) -> bool {
+    let base_ty = base.ty(body.local_decls(), self.tcx);
+    let Some(adt_def) = base_ty.ty.ty_adt_def() else {
+      return false;
+    };
+    let variant = adt_def.variant(base_ty.variant_index.unwrap_or(FIRST_VARIANT));
+    self.is_secret_field_def(variant.fields[field].did)
+  }
+
+  fn is_secret_field_def(&self, field_def_id: DefId) -> bool {
+    self
+      .config
+      .is_source_field(&self.tcx.def_path_str(field_def_id))
+      || is_secret(self.tcx, field_def_id)
+  }
+
+  /// Returns true if `ty` implements the trait of `flowistry_ifc_traits` with the given name.
+  fn implements(
+    &self,
+    param_env: ParamEnv<'tcx>,
+    ty: Ty<'tcx>,
+    trait_name: &str,
+  ) -> bool {
+    self.ifc_items.get(trait_name).is_some_and(|trait_def_id| {
+      implements_trait(self.tcx, param_env, ty, *trait_def_id, &[])
     })
-    .unwrap();
+  }
 
-  let mut errors = Vec::new();
-  for secure in secure_places.iter() {
-    let secure_deps = results.analysis.deps_for(&final_state, *secure);
-    for insecure in insecure_places.iter() {
-      let insecure_deps = results.analysis.deps_for(&final_state, *insecure);
-      if insecure_deps.is_superset(&secure_deps) {
-        errors.push((secure, insecure));
+  /// Returns the labels of a value of type `ty`, including the labels of the data it holds or
+  /// points to, see [`contained_types`]. A type parameter has the labels of its bounds.
+  fn labels_of(&self, param_env: ParamEnv<'tcx>, ty: Ty<'tcx>) -> Vec<Label> {
+    let mut labels = FxIndexSet::default();
+    for ty in contained_types(ty) {
+      if self.implements(param_env, ty, "Secure") {
+        labels.insert(Label::Secure);
       }
+      labels.extend(self.lattice.labels_of(param_env, ty));
     }
+    labels.into_iter().collect()
   }
 
-  let mut stdout = StandardStream::stderr(ColorChoice::Auto);
-  let mut black_spec = ColorSpec::new();
-  black_spec.set_fg(Some(Color::Yellow));
-  let mut red_spec = ColorSpec::new();
-  red_spec.set_fg(Some(Color::Red));
+  /// Returns true if a value of type `ty` contains a secret field or a field with labeled data,
+  /// possibly behind references.
+  fn contains_secret_fields(&self, param_env: ParamEnv<'tcx>, ty: Ty<'tcx>) -> bool {
+    let mut visited = HashSet::default();
+    let mut queue = vec![ty];
+    while let Some(ty) = queue.pop() {
+      for ty in ty.walk().filter_map(|arg| arg.as_type()) {
+        let TyKind::Adt(adt_def, args) = ty.kind() else {
+          continue;
+        };
+        if !visited.insert(adt_def.did()) {
+          continue;
+        }
+        for field in adt_def.all_fields() {
+          let field_ty = field.ty(self.tcx, args);
+          if self.is_secret_field_def(field.did)
+            || !self.labels_of(param_env, field_ty).is_empty()
+          {
+            return true;
+          }
+          queue.push(field_ty);
+        }
+      }
+    }
+    false
+  }
 
-  let decls = body.local_decls();
-  let source_map = tcx.sess.source_map();
-  let filename = match source_map.span_to_filename(body.span) {
-    FileName::Real(f) => f,
-    _ => unimplemented!(),
-  };
-  let has_errors = !errors.is_empty();
-  for (src, dst) in errors {
-    let body_span = tcx.hir_span_with_body(body_id.hir_id);
-    let src_span = decls[src.local].source_info.span.as_local(body_span);
-    let dst_span = decls[dst.local].source_info.span.as_local(body_span);
-
-    let span_range = |span| match span {
-      Some(span) => {
-        let lines = source_map.span_to_lines(span).unwrap();
-        let first = lines.lines.first().unwrap();
-        let last = lines.lines.last().unwrap();
-        format!(
-          "{}:{}-{}:{}",
-          first.line_index + 1,
-          first.start_col.0,
-          last.line_index + 1,
-          last.end_col.0
-        )
+  /// Returns the sources and sinks of a body.
+  fn policy(
+    &self,
+    body_id: &BodyId,
+    results: &FlowResults<'_, 'tcx>,
+  ) -> (Vec<Source<'tcx>>, Vec<Sink<'tcx>>) {
+    let Checker { tcx, config, .. } = self;
+    let tcx = *tcx;
+    let body = results.analysis.body;
+    let def_id = tcx.hir_body_owner_def_id(*body_id).to_def_id();
+    let param_env = tcx.param_env(def_id);
+    let typing_env = TypingEnv::post_analysis(tcx, def_id);
+    let location_domain = results.analysis.location_domain();
+
+    let all_places = body
+      .local_decls()
+      .indices()
+      .flat_map(|local| {
+        let place = Place::from_local(local, tcx);
+        place.interior_places(tcx, body, def_id)
+      })
+      .collect::<PlaceSet>();
+
+    let place_span = |place: Place| body.local_decls[place.local].source_info.span;
+    let mut sources = Vec::new();
+    let mut sinks = Vec::new();
+
+    // A place whose data has a label is a source, and a place with a user-defined label is also
+    // a sink wherever it is written. Every field of a parameter has the same dependencies as the
+    // parameter, so the fields of parameters are handled with the reads below. If a place and
+    // one of its fields have the same label, only the field is a source, so that a value of type
+    // `(Password, String)` does not make the `String` secret.
+    let is_arg = |local: Local| (1 ..= body.arg_count).contains(&local.as_usize());
+    let mut label_cache = HashMap::default();
+    let mut labels_of = |ty: Ty<'tcx>| -> Vec<Label> {
+      label_cache
+        .entry(ty)
+        .or_insert_with(|| self.labels_of(param_env, ty))
+        .clone()
+    };
+    let mut place_labels = Vec::new();
+    for place in all_places.iter() {
+      if !is_arg(place.local) || place.projection.is_empty() {
+        let ty = place.ty(body.local_decls(), tcx).ty;
+        place_labels.extend(labels_of(ty).into_iter().map(|label| (*place, label)));
       }
-      None => "<in macro expansion>".to_owned(),
+    }
+    let has_labeled_field = |place: &Place<'tcx>, label: &Label| {
+      place_labels.iter().any(|(other, other_label)| {
+        other_label == label
+          && other.local == place.local
+          && other.projection.len() > place.projection.len()
+          && other.projection.starts_with(place.projection)
+      })
     };
+    let mut labeled_places = Vec::new();
+    for (place, label) in &place_labels {
+      if has_labeled_field(place, label) {
+        continue;
+      }
+      sources.push(Source {
+        span: place_span(*place),
+        label: *label,
+        data: SourceData::Place(*place),
+        path: Vec::new(),
+        field: place
+          .projection
+          .iter()
+          .any(|elem| matches!(elem, ProjectionElem::Field(..))),
+      });
+      if matches!(label, Label::User(_)) {
+        labeled_places.push((*place, *label));
+      }
+    }
+
+    for location in body.all_locations() {
+      let written = match body.stmt_at(location) {
+        Either::Left(Statement {
+          kind: StatementKind::Assign(box (place, _)),
+          ..
+        }) => place,
+        Either::Right(Terminator {
+          kind: TerminatorKind::Call { destination, .. },
+          ..
+        }) => destination,
+        _ => continue,
+      };
+      for (place, label) in &labeled_places {
+        if place.local == written.local {
+          sinks.push(Sink {
+            span: body.source_info(location).span,
+            label: *label,
+            location,
+            place: *place,
+            kind: SinkKind::Data,
+            path: Vec::new(),
+            class: FlowClass::Explicit,
+          });
+        }
+      }
+    }
+
+    // Secret fields of local values are sources. Every field of a parameter (or of a value behind
+    // a reference) has the same dependencies, so the reads of those fields are sources instead.
+    for place in all_places.iter().filter(|place| !is_arg(place.local)) {
+      let Some((base, ProjectionElem::Field(field, _))) = place.iter_projections().last()
+      else {
+        continue;
+      };
+      if self.is_secret_field(body, base, field) {
+        sources.push(Source {
+          span: place_span(*place),
+          label: Label::Secure,
+          data: SourceData::Place(*place),
+          path: Vec::new(),
+          field: true,
+        });
+      }
+    }
+
+    // The same goes for fields with labeled data, unless the whole value is already a source.
+    let mut reads = PlaceReads { reads: Vec::new() };
+    reads.visit_body(body);
+    for (place, location) in reads.reads {
+      let local_labels = labels_of(body.local_decls[place.local].ty);
+      let mut labels = FxIndexSet::default();
+      for (base, elem) in place.iter_projections() {
+        let ProjectionElem::Field(field, field_ty) = elem else {
+          continue;
+        };
+        if !is_arg(base.local) && !base.projection.contains(&ProjectionElem::Deref) {
+          continue;
+        }
+        if self.is_secret_field(body, base, field) {
+          labels.insert(Label::Secure);
+        }
+        labels.extend(
+          labels_of(field_ty)
+            .into_iter()
+            .filter(|label| !local_labels.contains(label)),
+        );
+      }
+      for label in labels {
+        let mut deps = LocationOrArgSet::new(location_domain);
+        deps.insert(location);
+        sources.push(Source {
+          span: body.source_info(location).span,
+          label,
+          data: SourceData::Deps(deps),
+          path: Vec::new(),
+          field: true,
+        });
+      }
+    }
+
+    let function_path = tcx.def_path_str(def_id);
+    let annotated_params = tcx
+      .hir_body(*body_id)
+      .params
+      .iter()
+      .enumerate()
+      .filter(|(_, param)| {
+        tcx
+          .hir_attrs(param.hir_id)
+          .iter()
+          .any(|attr| attr.path_matches(&secret_attr()))
+      })
+      .map(|(index, _)| index);
+    let mut source_params = config.source_params(&function_path).collect::<Vec<_>>();
+    source_params.extend(annotated_params);
+    source_params.sort();
+    source_params.dedup();
+    for index in source_params {
+      if index >= body.arg_count {
+        log::warn!("{function_path} has no parameter {index}");
+        continue;
+      }
+      let local = Local::from_usize(index + 1);
+      let mut deps = LocationOrArgSet::new(location_domain);
+      deps.insert(LocationOrArg::Arg(local));
+      sources.push(Source {
+        span: body.local_decls[local].source_info.span,
+        label: Label::Secure,
+        data: SourceData::Deps(deps),
+        path: Vec::new(),
+        field: false,
+      });
+    }
+
+    for (block, data) in body.basic_blocks.iter_enumerated() {
+      let TerminatorKind::Call {
+        func,
+        args,
+        destination,
+        ..
+      } = &data.terminator().kind
+      else {
+        continue;
+      };
+      let location = body.terminator_loc(block);
+      let span = data.terminator().source_info.span;
+      let callees = callees(tcx, typing_env, func);
+      let callee_paths = callee_paths(tcx, typing_env, func);
+      let summaries = callees
+        .iter()
+        .filter_map(|def_id| self.summaries.get(def_id))
+        .collect::<Vec<_>>();
+
+      let call_deps = || {
+        let mut deps = LocationOrArgSet::new(location_domain);
+        deps.insert(location);
+        SourceData::Deps(deps)
+      };
+      if config.is_source_return(&callee_paths)
+        || callees.iter().any(|callee| is_secret(tcx, *callee))
+      {
+        sources.push(Source {
+          span,
+          label: Label::Secure,
+          data: call_deps(),
+          path: Vec::new(),
+          field: false,
+        });
+      }
+      // The return value of a declassifier is not secret, see `declassifier_summaries`.
+      let declassified = self.implements(
+        param_env,
+        destination.ty(body.local_decls(), tcx).ty,
+        "Declassified",
+      );
+      for summary in summaries.iter().filter(|_| !declassified) {
+        for (label, path) in &summary.return_sources {
+          sources.push(Source {
+            span,
+            label: *label,
+            data: call_deps(),
+            path: path.clone(),
+            field: false,
+          });
+        }
+      }
+
+      // An argument is a sink if it has an insecure type, the callee is a configured sink,
+      // or the callee passes it to a sink.
+      let config_sinks = config.sinks_for(&callee_paths);
+      for (index, arg) in args.iter().enumerate() {
+        let Some(place) = arg.node.as_place() else {
+          continue;
+        };
+        let ty = place.ty(body.local_decls(), tcx).ty;
+        let insecure = contained_types(ty)
+          .into_iter()
+          .any(|ty| self.implements(param_env, ty, "Insecure"));
+        if insecure || config_sinks.iter().any(|sink| sink.includes_arg(index)) {
+          sinks.push(Sink {
+            span,
+            label: Label::Insecure,
+            location,
+            place,
+            kind: SinkKind::Data,
+            path: Vec::new(),
+            class: FlowClass::Explicit,
+          });
+        }
+        for summary in &summaries {
+          for ((_, label), param_sink) in summary
+            .param_sinks
+            .iter()
+            .filter(|((param, _), _)| *param == index)
+          {
+            sinks.push(Sink {
+              span,
+              label: *label,
+              location,
+              place,
+              kind: SinkKind::Data,
+              path: param_sink.path.clone(),
+              class: param_sink.class,
+            });
+          }
+        }
+      }
+    }
+
+    (sources, sinks)
+  }
+
+  /// Returns the branch conditions and the array indices of a body, whose dependencies
+  /// determine the control flow and the memory accesses of the body.
+  fn timing_sinks(&self, body: &Body<'tcx>) -> Vec<Sink<'tcx>> {
+    let mut sinks = Vec::new();
+    for (block, data) in body.basic_blocks.iter_enumerated() {
+      let terminator = data.terminator();
+      if let TerminatorKind::SwitchInt { discr, .. } = &terminator.kind
+        && let Some(place) = discr.as_place()
+      {
+        sinks.push(Sink {
+          span: terminator.source_info.span,
+          label: Label::Insecure,
+          location: body.terminator_loc(block),
+          place,
+          kind: SinkKind::Branch,
+          path: Vec::new(),
+          class: FlowClass::Explicit,
+        });
+      }
+    }
+
+    let mut index_locals = IndexLocals {
+      indices: Vec::new(),
+    };
+    index_locals.visit_body(body);
+    for (local, location) in index_locals.indices {
+      sinks.push(Sink {
+        span: body.source_info(location).span,
+        label: Label::Insecure,
+        location,
+        place: Place::from_local(local, self.tcx),
+        kind: SinkKind::Index,
+        path: Vec::new(),
+        class: FlowClass::Explicit,
+      });
+    }
+    sinks
+  }
 
-    let span_contents = |span| match span {
-      Some(span) => source_map.span_to_snippet(span).unwrap(),
-      None => "<in macro expansion>".to_owned(),
+  /// Returns the insecure flows of a body.
+  pub(crate) fn check(&self, flow: &BodyFlow<'_, 'tcx>) -> Vec<InsecureFlow> {
+    let tcx = self.tcx;
+    let lattice = &self.lattice;
+    let body_id = &flow.body_id;
+    let results = &flow.results;
+    let body = results.analysis.body;
+    let param_env = tcx.param_env(tcx.hir_body_owner_def_id(*body_id));
+    let (sources, sinks) = self.policy(body_id, results);
+    let branches = Branches::new(tcx, *body_id, body);
+
+    let mut errors = Vec::new();
+    for sink in &sinks {
+      let state = results.state_at(sink.location);
+      let sink_deps = results.analysis.deps_for(state, sink.place);
+      // A callee that receives a value with secret fields checks the reads of those fields.
+      let passes_secret_fields = !sink.path.is_empty()
+        && self
+          .contains_secret_fields(param_env, sink.place.ty(body.local_decls(), tcx).ty);
+      let mut flows = Vec::new();
+      for source in &sources {
+        if lattice.flows_to(source.label, sink.label)
+          || (source.field && passes_secret_fields)
+        {
+          continue;
+        }
+        let source_deps = source.deps(results, state);
+        if !source_deps.is_empty() && sink_deps.is_superset(&source_deps) {
+          flows.push((source, source_deps));
+        } else if branches.skips(
+          results,
+          |state| source.deps(results, state),
+          sink.location,
+        ) {
+          errors.push((source, sink, Some(FlowClass::Termination)));
+        }
+      }
+      errors.extend(original_sources(flows).map(|source| (source, sink, None)));
+    }
+
+    // Only data dependencies are checked for timing flows, since a branch inside of a branch on
+    // a secret is only reached through the outer branch, which is reported itself.
+    let timing_sinks = self.timing_sinks(body);
+    for sink in &timing_sinks {
+      let state = results.state_at(sink.location);
+      let sink_deps = results.analysis.deps_for(state, sink.place);
+      let mut flows = Vec::new();
+      for source in &sources {
+        if lattice.flows_to(source.label, sink.label) {
+          continue;
+        }
+        let source_deps = source.deps(results, state);
+        if !source_deps.is_empty()
+          && sink_deps.is_superset(&source_deps)
+          && flow.is_explicit(
+            &branches,
+            |results, state| source.deps(results, state),
+            sink,
+          )
+        {
+          flows.push((source, source_deps));
+        }
+      }
+      errors.extend(
+        original_sources(flows).map(|source| (source, sink, Some(FlowClass::Timing))),
+      );
+    }
+
+    // Spans inside of macros are reported at the macro call in the body, e.g. for `insecure_print!`.
+    let body_span = tcx.hir_span_with_body(tcx.hir_body_owner(*body_id));
+    let local_span = |span: Span| span.as_local(body_span).unwrap_or(span);
+
+    let crate_name = tcx.crate_name(LOCAL_CRATE).to_string();
+    let function = tcx.def_path_str(tcx.hir_body_owner_def_id(*body_id));
+    let mut flows = Vec::new();
+    let mut reported = FxIndexSet::default();
+    for (source, sink, class) in errors {
+      let source_span = local_span(source.span);
+      let sink_span = local_span(sink.span);
+      if !reported.insert((source_span, sink_span)) {
+        continue;
+      }
+
+      let ClearCrossCrate::Set(scope_data) =
+        &body.source_scopes[body.source_info(sink.location).scope].local_data
+      else {
+        continue;
+      };
+      let source_label = lattice.describe(source.label);
+      let sink_label = lattice.describe(sink.label);
+
+      let class = class.unwrap_or_else(|| {
+        flow.classify(
+          &branches,
+          |results, state| source.deps(results, state),
+          sink,
+          sink_span,
+        )
+      });
+      let lint = class_lint(class);
+      let lint_root = scope_data.lint_root;
+      let message = match (class, sink.kind) {
+        (_, SinkKind::Branch) => {
+          format!("branch condition depends on {source_label} data")
+        }
+        (_, SinkKind::Index) => format!("array index depends on {source_label} data"),
+        (FlowClass::Implicit, _) => {
+          format!("implicit flow from {source_label} data to {sink_label} data")
+        }
+        (FlowClass::Termination, _) => format!(
+          "whether {sink_label} data is written depends on the termination of a branch on \
+           {source_label} data"
+        ),
+        _ => format!("insecure flow from {source_label} data to {sink_label} data"),
+      };
+      let sink_note = match sink.kind {
+        SinkKind::Data => format!("flows into {sink_label} data here"),
+        SinkKind::Branch => "branches on it here".to_string(),
+        SinkKind::Index => "indexes memory with it here".to_string(),
+      };
+      let level = match tcx.lint_level_at_node(lint, lint_root).level {
+        Level::Deny | Level::Forbid => "error",
+        Level::Warn | Level::ForceWarn => "warning",
+        Level::Allow | Level::Expect => "none",
+      };
+
+      let range = |span: Span| SourceRange::from_span(tcx, span);
+      let path = (source.path.iter().rev())
+        .chain([&source_span, &sink_span])
+        .chain(&sink.path)
+        .map(|span| range(*span))
+        .collect();
+      let finding = Finding {
+        fingerprint: String::new(),
+        rule_id: lint.name_lower(),
+        class,
+        level: level.to_string(),
+        message,
+        crate_name: crate_name.clone(),
+        function: function.clone(),
+        source: range(source_span),
+        sink: range(sink_span),
+        path,
+      };
+
+      flows.push(InsecureFlow {
+        finding,
+        lint,
+        lint_root,
+        source_span,
+        sink_span,
+        source_note: format!("{source_label} data comes from here"),
+        sink_note,
+        source_path: source.path.clone(),
+        sink_path: sink.path.clone(),
+      });
+    }
+
+    report::set_fingerprints(flows.iter_mut().map(|flow| &mut flow.finding));
+    flows
+  }
+}
+
+/// Returns the sources of the flows into a sink, except for the sources derived from another
+/// source with the same label, e.g. a reference to a secret. `flows` has the dependencies of
+/// each source at the sink.
+fn original_sources<'a, 'tcx>(
+  flows: Vec<(&'a Source<'tcx>, LocationOrArgSet)>,
+) -> impl Iterator<Item = &'a Source<'tcx>> {
+  let derived = |index: usize| {
+    let (source, deps) = &flows[index];
+    flows
+      .iter()
+      .enumerate()
+      .any(|(other_index, (other, other_deps))| {
+        other_index != index
+          && other.label == source.label
+          && deps.is_superset(other_deps)
+          && (other_index < index || !other_deps.is_superset(deps))
+      })
+  };
+  let original = (0 .. flows.len())
+    .filter(|index| !derived(*index))
+    .collect::<Vec<_>>();
+  original.into_iter().map(move |index| flows[index].0)
+}
+
+/// Computes the information flow of a body, treating declassifiers as cutting the flow
+/// from their arguments.
+fn compute_flow<'a, 'tcx: 'a>(
+  tcx: TyCtxt<'tcx>,
+  body_id: BodyId,
+  eval_mode: EvalMode,
+) -> FlowResults<'a, 'tcx> {
+  let def_id = tcx.hir_body_owner_def_id(body_id);
+  let body_with_facts = borrowck_facts::get_body_with_borrowck_facts(tcx, def_id);
+  let summaries = declassifier_summaries(tcx, &body_with_facts.body, def_id.to_def_id());
+  fluid_set!(FLOW_SUMMARIES, summaries);
+  fluid_set!(EVAL_MODE, eval_mode);
+  infoflow::compute_flow(tcx, body_id, body_with_facts)
+}
+
+/// The information flow of a body, and its flow without control dependencies, which is only
+/// computed to classify flows through a branch on their source.
+pub(crate) struct BodyFlow<'a, 'tcx> {
+  tcx: TyCtxt<'tcx>,
+  body_id: BodyId,
+  eval_mode: EvalMode,
+  results: FlowResults<'a, 'tcx>,
+  explicit_results: OnceCell<FlowResults<'a, 'tcx>>,
+}
+
+impl<'a, 'tcx: 'a> BodyFlow<'a, 'tcx> {
+  fn new(tcx: TyCtxt<'tcx>, body_id: BodyId, eval_mode: EvalMode) -> Self {
+    BodyFlow {
+      tcx,
+      body_id,
+      eval_mode,
+      results: compute_flow(tcx, body_id, eval_mode),
+      explicit_results: OnceCell::new(),
+    }
+  }
+
+  fn explicit_results(&self) -> &FlowResults<'a, 'tcx> {
+    self.explicit_results.get_or_init(|| {
+      let explicit_mode = EvalMode {
+        control_mode: ControlMode::Ignore,
+        ..self.eval_mode
+      };
+      compute_flow(self.tcx, self.body_id, explicit_mode)
+    })
+  }
+
+  /// Returns true if the sink depends on a source with dependencies `source_deps` through data
+  /// dependencies alone, i.e. if the flow remains without control dependencies. That is always
+  /// the case if it does not go through a branch on the source.
+  fn is_explicit(
+    &self,
+    branches: &Branches<'_, 'tcx>,
+    source_deps: impl Fn(&FlowResults<'a, 'tcx>, &FlowDomain<'tcx>) -> LocationOrArgSet,
+    sink: &Sink<'tcx>,
+  ) -> bool {
+    let results = &self.results;
+    let sink_deps = results
+      .analysis
+      .deps_for(results.state_at(sink.location), sink.place);
+    if !branches.through_branch(results, |state| source_deps(results, state), &sink_deps)
+    {
+      return true;
+    }
+    let explicit_results = self.explicit_results();
+    let state = explicit_results.state_at(sink.location);
+    let source_deps = source_deps(explicit_results, state);
+    !source_deps.is_empty()
+      && explicit_results
+        .analysis
+        .deps_for(state, sink.place)
+        .is_superset(&source_deps)
+  }
+
+  /// Classifies the flow into the sink from a source with dependencies `source_deps`, see
+  /// [`class`]. The flow is at most as strong as the sink's flow in the callees.
+  fn classify(
+    &self,
+    branches: &Branches<'_, 'tcx>,
+    source_deps: impl Fn(&FlowResults<'a, 'tcx>, &FlowDomain<'tcx>) -> LocationOrArgSet,
+    sink: &Sink<'tcx>,
+    sink_span: Span,
+  ) -> FlowClass {
+    let class = if self.is_explicit(branches, &source_deps, sink) {
+      FlowClass::Explicit
+    } else {
+      let results = &self.results;
+      branches.classify(
+        results,
+        |state| source_deps(results, state),
+        sink.location,
+        sink_span,
+      )
     };
+    class::weakest(class, sink.class)
+  }
+}
 
-    stdout.set_color(&red_spec)?;
-    writeln!(
-      stdout,
-      "ERROR: insecure flow in {filename} from data at {src_span}:",
-      filename = filename
-        .local_path_if_available()
-        .file_name()
-        .unwrap()
-        .to_string_lossy(),
-      src_span = span_range(src_span)
-    )?;
-
-    stdout.set_color(&black_spec)?;
-    writeln!(
-      stdout,
-      "  {src_snippet}",
-      src_snippet = span_contents(src_span)
-    )?;
-
-    stdout.set_color(&red_spec)?;
-    writeln!(
-      stdout,
-      "to data at {dst_span}:",
-      dst_span = span_range(dst_span)
-    )?;
-
-    stdout.set_color(&black_spec)?;
-    writeln!(
-      stdout,
-      "  {dst_snippet}\n",
-      dst_snippet = span_contents(dst_span)
-    )?;
-  }
-
-  Ok(if has_errors {
-    IssueFound::Yes
-  } else {
-    IssueFound::No
-  })
+/// Checks every body of the crate whose path matches `function`, if any.
+pub(crate) fn check_crate(
+  tcx: TyCtxt,
+  config: &IfcConfig,
+  function: Option<&Pattern>,
+  eval_mode: EvalMode,
+) -> Vec<InsecureFlow> {
+  let mut checker = Checker::new(tcx, config);
+  let bodies = find_bodies(tcx)
+    .into_iter()
+    .filter(|(_, body_id)| {
+      function.is_none_or(|pattern| {
+        pattern.matches(&tcx.def_path_str(tcx.hir_body_owner_def_id(*body_id)))
+      })
+    })
+    .map(|(_, body_id)| BodyFlow::new(tcx, body_id, eval_mode))
+    .collect::<Vec<_>>();
+  checker.compute_summaries(&bodies);
+  let flows = bodies.iter().flat_map(|flow| checker.check(flow)).collect();
+
+  // The unchecked bodies may contain the items of the unmatched entries.
+  let unmatched = config.unmatched();
+  if function.is_none() {
+    for spec in unmatched {
+      tcx.dcx().warn(format!(
+        "IFC config entry {spec} matches nothing in this crate"
+      ));
+    }
+  }
+  flows
+}
+
+/// An insecure flow found by [`Checker::check`], which can be reported as a [`Finding`]
+/// and as a lint for its class.
+pub(crate) struct InsecureFlow {
+  pub(crate) finding: Finding,
+  lint: &'static Lint,
+  lint_root: HirId,
+  source_span: Span,
+  sink_span: Span,
+  source_note: String,
+  sink_note: String,
+  source_path: Vec<Span>,
+  sink_path: Vec<Span>,
+}
+
+impl InsecureFlow {
+  /// Returns true if the flow's lint is allowed or expected, e.g. with
+  /// `#[allow(insecure_flow)]` in the source code or `--implicit-flows allow`.
+  pub(crate) fn is_suppressed(&self, tcx: TyCtxt) -> bool {
+    matches!(
+      tcx.lint_level_at_node(self.lint, self.lint_root).level,
+      Level::Allow | Level::Expect
+    )
+  }
+
+  /// Emits the flow as a diagnostic at the lint level of the sink.
+  pub(crate) fn emit(&self, tcx: TyCtxt) {
+    let InsecureFlow {
+      source_span,
+      sink_span,
+      source_note,
+      sink_note,
+      ..
+    } = self;
+    tcx.node_span_lint(self.lint, self.lint_root, *sink_span, |diag| {
+      diag.primary_message(self.finding.message.clone());
+      diag.span_label(*sink_span, sink_note.clone());
+      diag.span_label(*source_span, source_note.clone());
+      for span in &self.source_path {
+        diag.span_note(*span, "the returned data comes from here");
+      }
+      for span in &self.sink_path {
+        diag.span_note(*span, "the argument flows into a sink here");
+      }
+    });
+  }
 }
@@ -0,0 +1,170 @@
+//! Baselines of accepted IFC findings.
+//!
+//! A baseline records the [fingerprints](crate::report::set_fingerprints) of known findings, so
+//! `cargo ifc` can be adopted on an existing codebase and only report new findings.
+//! `cargo ifc --update-baseline` writes the current findings to the baseline file, replacing the
+//! previous entries of each checked crate, and later runs skip every finding in the baseline.
+//!
+//! A single finding can also be suppressed in the source code, like any other lint, with
+//! `#[allow(insecure_flow)]` or `#[expect(insecure_flow)]` on the enclosing item or statement.
+//! `cargo ifc` sets `--cfg flowistry_ifc`, so `#[cfg_attr(flowistry_ifc, allow(insecure_flow))]`
+//! avoids unknown lint warnings in normal builds (along with
+//! `unexpected_cfgs = { level = "warn", check-cfg = ["cfg(flowistry_ifc)"] }` in the
+//! `[lints.rust]` table of `Cargo.toml`).
+
+use std::{
+  fs::{self, OpenOptions},
+  io::{Read, Seek, Write},
+  path::Path,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::report::Finding;
+
+/// The default name of a baseline file, relative to the workspace root.
+pub const DEFAULT_BASELINE_FILE: &str = "flowistry-ifc-baseline.json";
+
+/// An accepted finding. Only the fingerprint is used for matching, the other fields
+/// are for reviewers of the baseline file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+  pub fingerprint: String,
+  pub crate_name: String,
+  pub function: String,
+  pub source: String,
+  pub sink: String,
+}
+
+impl From<&Finding> for BaselineEntry {
+  fn from(finding: &Finding) -> Self {
+    BaselineEntry {
+      fingerprint: finding.fingerprint.clone(),
+      crate_name: finding.crate_name.clone(),
+      function: finding.function.clone(),
+      source: finding.source.snippet.clone(),
+      sink: finding.sink.snippet.clone(),
+    }
+  }
+}
+
+/// The contents of a baseline file, see the [module-level documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+  #[serde(default)]
+  pub findings: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+  /// Reads a baseline from a JSON file, or returns an empty baseline if the file does not exist.
+  pub fn load(path: &Path) -> Result<Self> {
+    if !path.exists() {
+      return Ok(Baseline::default());
+    }
+    let contents = fs::read_to_string(path)
+      .with_context(|| format!("Failed to read IFC baseline from {}", path.display()))?;
+    Self::parse(&contents)
+  }
+
+  fn parse(contents: &str) -> Result<Self> {
+    if contents.trim().is_empty() {
+      return Ok(Baseline::default());
+    }
+    serde_json::from_str(contents).context("Failed to parse IFC baseline")
+  }
+
+  /// Returns true if the baseline accepts `finding`.
+  pub fn contains(&self, finding: &Finding) -> bool {
+    self
+      .findings
+      .iter()
+      .any(|entry| entry.fingerprint == finding.fingerprint)
+  }
+
+  /// Replaces the entries of `crate_name` in the baseline file with `findings`.
+  ///
+  /// The file is locked while it is updated, since cargo checks the crates of a workspace
+  /// in parallel.
+  pub fn update(path: &Path, crate_name: &str, findings: &[Finding]) -> Result<()> {
+    let mut file = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .create(true)
+      .truncate(false)
+      .open(path)
+      .with_context(|| format!("Failed to open IFC baseline {}", path.display()))?;
+    file.lock()?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    let mut baseline = Self::parse(&contents)?;
+    baseline
+      .findings
+      .retain(|entry| entry.crate_name != crate_name);
+    for finding in findings {
+      let entry = BaselineEntry::from(finding);
+      if !baseline.findings.contains(&entry) {
+        baseline.findings.push(entry);
+      }
+    }
+    baseline.findings.sort_by(|a, b| {
+      (&a.crate_name, &a.function, &a.fingerprint).cmp(&(
+        &b.crate_name,
+        &b.function,
+        &b.fingerprint,
+      ))
+    });
+
+    file.set_len(0)?;
+    file.rewind()?;
+    writeln!(file, "{}", serde_json::to_string_pretty(&baseline)?)?;
+    file.unlock()?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::env;
+
+  use super::*;
+  use crate::report::{self, test::finding};
+
+  #[test]
+  fn test_baseline() {
+    let path = env::temp_dir().join(format!(
+      "flowistry-ifc-baseline-{}.json",
+      std::process::id()
+    ));
+    let _ = fs::remove_file(&path);
+
+    let mut findings = [
+      finding("example::login", 2, "log(password)"),
+      finding("example::login", 3, "log(password)"),
+    ];
+    report::set_fingerprints(findings.iter_mut());
+    let mut other = finding("other::login", 2, "log(password)");
+    other.crate_name = "other".to_string();
+    report::set_fingerprints([&mut other]);
+
+    // Each crate's entries are kept when another crate is updated.
+    Baseline::update(&path, "example", &findings[.. 1]).unwrap();
+    Baseline::update(&path, "other", std::slice::from_ref(&other)).unwrap();
+    let baseline = Baseline::load(&path).unwrap();
+    assert_eq!(baseline.findings.len(), 2);
+    assert!(baseline.contains(&findings[0]));
+    assert!(!baseline.contains(&findings[1]));
+    assert!(baseline.contains(&other));
+
+    // Updating a crate replaces its previous entries.
+    Baseline::update(&path, "example", &findings[1 ..]).unwrap();
+    let baseline = Baseline::load(&path).unwrap();
+    assert!(!baseline.contains(&findings[0]));
+    assert!(baseline.contains(&findings[1]));
+    assert!(baseline.contains(&other));
+
+    fs::remove_file(&path).unwrap();
+    assert_eq!(Baseline::load(&path).unwrap(), Baseline::default());
+  }
+}
@@ -0,0 +1,241 @@
+//! Classification of insecure flows.
+//!
+//! A flow is *explicit* if the sink depends on the source through data dependencies alone.
+//! A control dependency adds the branch to the dependencies of everything it controls, so a
+//! flow is explicit if the sink does not depend on a branch on the source, see
+//! [`Branches::through_branch`]. Otherwise the checker analyzes the body a second time with
+//! [`ControlMode::Ignore`](flowistry::extensions::ControlMode::Ignore). If the flow is not
+//! explicit, the sink depends on the source through a branch whose condition depends on the
+//! source. The flow is *implicit* if the sink is inside that branch, e.g.
+//! `if secret { print() }`, and *termination-sensitive* if the branch is a loop condition or
+//! the sink comes after the branch, e.g. `if secret { return; } print()`. Termination-sensitive
+//! flows only leak whether (or how long) the program runs, so they are often acceptable.
+//!
+//! A flow into a call whose callee passes the argument to a sink is classified in the caller
+//! and in the callee, and has the [weaker](weakest) of both classes. For example, passing a
+//! secret to `fn f(flag: bool) { if flag { print() } }` is an implicit flow.
+
+use flowistry::infoflow::{FlowDomain, FlowResults};
+use rustc_data_structures::graph::Successors;
+use rustc_hir::{
+  BodyId, Expr, ExprKind,
+  intravisit::{self, Visitor},
+};
+use rustc_index::bit_set::DenseBitSet;
+use rustc_middle::{
+  mir::{BasicBlock, Body, Location, Operand, TerminatorKind},
+  ty::TyCtxt,
+};
+use rustc_span::Span;
+use rustc_utils::{
+  BodyExt, OperandExt, SpanExt,
+  mir::{
+    control_dependencies::ControlDependencies,
+    location_or_arg::{LocationOrArg, index::LocationOrArgSet},
+  },
+};
+
+use crate::report::FlowClass;
+
+/// Collects the spans of the expressions that can branch.
+struct BranchCollector {
+  spans: Vec<Span>,
+}
+
+impl<'tcx> Visitor<'tcx> for BranchCollector {
+  fn visit_expr(&mut self, expr: &'tcx Expr<'tcx>) {
+    if matches!(
+      expr.kind,
+      ExprKind::If(..) | ExprKind::Match(..) | ExprKind::Loop(..)
+    ) {
+      self.spans.push(expr.span);
+    }
+    intravisit::walk_expr(self, expr);
+  }
+}
+
+/// Returns how strong a flow of the given class is, from 0 for timing flows (which are not
+/// flows into a sink) to 3 for explicit flows.
+pub(crate) fn strength(class: FlowClass) -> u8 {
+  match class {
+    FlowClass::Timing => 0,
+    FlowClass::Termination => 1,
+    FlowClass::Implicit => 2,
+    FlowClass::Explicit => 3,
+  }
+}
+
+/// Returns the weaker of two classes, e.g. `Implicit` for a flow into an argument that only
+/// reaches the sink through a branch in the callee.
+pub(crate) fn weakest(a: FlowClass, b: FlowClass) -> FlowClass {
+  if strength(a) <= strength(b) { a } else { b }
+}
+
+/// The branches of a body, used to classify its insecure flows.
+pub(crate) struct Branches<'a, 'tcx> {
+  body: &'a Body<'tcx>,
+  body_span: Span,
+  control_dependencies: ControlDependencies<BasicBlock>,
+  expr_spans: Vec<Span>,
+}
+
+impl<'a, 'tcx> Branches<'a, 'tcx> {
+  pub(crate) fn new(tcx: TyCtxt<'tcx>, body_id: BodyId, body: &'a Body<'tcx>) -> Self {
+    let mut collector = BranchCollector { spans: Vec::new() };
+    intravisit::walk_body(&mut collector, tcx.hir_body(body_id));
+    Branches {
+      body,
+      body_span: tcx.hir_span_with_body(tcx.hir_body_owner(body_id)),
+      control_dependencies: body.control_dependencies(),
+      expr_spans: collector.spans,
+    }
+  }
+
+  /// Classifies a flow that is not explicit. `source_deps` returns the dependencies of the
+  /// source in a given state, and `sink_span` is the span of the sink in the body.
+  pub(crate) fn classify(
+    &self,
+    results: &FlowResults<'_, 'tcx>,
+    source_deps: impl Fn(&FlowDomain<'tcx>) -> LocationOrArgSet,
+    sink_location: Location,
+    sink_span: Span,
+  ) -> FlowClass {
+    let mut class = FlowClass::Implicit;
+    for block in self.controllers(sink_location.block).iter() {
+      let terminator = self.body.basic_blocks[block].terminator();
+      let TerminatorKind::SwitchInt { discr, .. } = &terminator.kind else {
+        continue;
+      };
+      if !self.branches_on(results, &source_deps, block, discr) {
+        continue;
+      }
+
+      let switch_span = terminator
+        .source_info
+        .span
+        .as_local(self.body_span)
+        .unwrap_or(terminator.source_info.span);
+      let inside_branch = self
+        .expr_spans
+        .iter()
+        .filter(|span| span.contains(switch_span))
+        .min_by_key(|span| span.hi() - span.lo())
+        .is_some_and(|span| span.contains(sink_span));
+      if inside_branch && !self.is_cyclic(block) {
+        return FlowClass::Implicit;
+      }
+      class = FlowClass::Termination;
+    }
+    class
+  }
+
+  /// Returns true if any of `deps` is a branch whose condition depends on the source.
+  ///
+  /// Only such a branch can make a place with dependencies `deps` depend on the source
+  /// through a control dependency, which adds the branch itself to the dependencies.
+  pub(crate) fn through_branch(
+    &self,
+    results: &FlowResults<'_, 'tcx>,
+    source_deps: impl Fn(&FlowDomain<'tcx>) -> LocationOrArgSet,
+    deps: &LocationOrArgSet,
+  ) -> bool {
+    deps.iter().any(|dep| {
+      let LocationOrArg::Location(location) = dep else {
+        return false;
+      };
+      let data = &self.body.basic_blocks[location.block];
+      if location.statement_index != data.statements.len() {
+        return false;
+      }
+      let TerminatorKind::SwitchInt { discr, .. } = &data.terminator().kind else {
+        return false;
+      };
+      self.branches_on(results, &source_deps, location.block, discr)
+    })
+  }
+
+  /// Returns true if a branch on the source that always runs before the sink can skip the sink,
+  /// e.g. `if secret { return; } print()`.
+  ///
+  /// Flowistry computes control dependencies with respect to each `return` separately, so the
+  /// sink does not depend on such a branch when the branch leads to a different `return`.
+  pub(crate) fn skips(
+    &self,
+    results: &FlowResults<'_, 'tcx>,
+    source_deps: impl Fn(&FlowDomain<'tcx>) -> LocationOrArgSet,
+    sink_location: Location,
+  ) -> bool {
+    let cfg = &self.body.basic_blocks;
+    let dominators = cfg.dominators();
+    cfg.indices().any(|block| {
+      let TerminatorKind::SwitchInt { discr, .. } = &cfg[block].terminator().kind else {
+        return false;
+      };
+      block != sink_location.block
+        && dominators.dominates(block, sink_location.block)
+        && self.branches_on(results, &source_deps, block, discr)
+        && cfg
+          .successors(block)
+          .any(|next| !self.reaches(next, sink_location.block))
+    })
+  }
+
+  /// Returns true if the condition of the switch terminating `block` depends on the source.
+  fn branches_on(
+    &self,
+    results: &FlowResults<'_, 'tcx>,
+    source_deps: impl Fn(&FlowDomain<'tcx>) -> LocationOrArgSet,
+    block: BasicBlock,
+    discr: &Operand<'tcx>,
+  ) -> bool {
+    let Some(discr) = discr.as_place() else {
+      return false;
+    };
+    let state = results.state_at(self.body.terminator_loc(block));
+    let source_deps = source_deps(state);
+    !source_deps.is_empty()
+      && results
+        .analysis
+        .deps_for(state, discr)
+        .is_superset(&source_deps)
+  }
+
+  /// Returns the blocks that `block` is transitively control-dependent on.
+  fn controllers(&self, block: BasicBlock) -> DenseBitSet<BasicBlock> {
+    let mut controllers = DenseBitSet::new_empty(self.body.basic_blocks.len());
+    let mut queue = vec![block];
+    while let Some(block) = queue.pop() {
+      let Some(dependent_on) = self.control_dependencies.dependent_on(block) else {
+        continue;
+      };
+      for controller in dependent_on.iter() {
+        if controllers.insert(controller) {
+          queue.push(controller);
+        }
+      }
+    }
+    controllers
+  }
+
+  /// Returns true if `block` is part of a loop.
+  fn is_cyclic(&self, block: BasicBlock) -> bool {
+    let cfg = &self.body.basic_blocks;
+    cfg.successors(block).any(|next| self.reaches(next, block))
+  }
+
+  /// Returns true if there is a path from `from` to `to`.
+  fn reaches(&self, from: BasicBlock, to: BasicBlock) -> bool {
+    let cfg = &self.body.basic_blocks;
+    let mut visited = DenseBitSet::new_empty(cfg.len());
+    let mut queue = vec![from];
+    while let Some(next) = queue.pop() {
+      if next == to {
+        return true;
+      }
+      if visited.insert(next) {
+        queue.extend(cfg.successors(next));
+      }
+    }
+    false
+  }
+}
@@ -0,0 +1,209 @@
+//! Declarative sources and sinks for the IFC checker.
+//!
+//! Besides types that implement the `Secure` and `Insecure` traits from `flowistry_ifc_traits`,
+//! the checker reads sources and sinks from a JSON config file, so code can be audited
+//! without adding marker impls. Functions and fields are identified by their paths as printed
+//! by [`TyCtxt::def_path_str`](rustc_middle::ty::TyCtxt::def_path_str), which omits the name of
+//! the checked crate for its own items, and a call matches a path if either the called item or
+//! the implementation it resolves to has that path. Arguments and parameters are numbered
+//! from 0. For example:
+//!
+//! ```json
+//! {
+//!   "sources": [
+//!     { "return": "std::env::var" },
+//!     { "param": { "function": "auth::login", "index": 1 } },
+//!     { "field": "User::password_hash" }
+//!   ],
+//!   "sinks": [
+//!     { "function": "std::io::Write::write_all", "args": [1] },
+//!     { "function": "reqwest::Client::post" }
+//!   ]
+//! }
+//! ```
+//!
+//! A sink without `args` applies to all of the function's arguments. The checker warns about
+//! sources and sinks that match nothing in a crate, since they are likely mistyped.
+//!
+//! The same sources can be declared in the code with the `#[ifc::secret]` attribute from
+//! `flowistry_ifc_traits`, on fields, parameters, and functions (for their return value).
+
+use std::{cell::RefCell, collections::HashSet, fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// The default name of a config file, relative to the workspace root.
+pub const DEFAULT_CONFIG_FILE: &str = "flowistry-ifc.json";
+
+/// Data that is secret.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceSpec {
+  /// The value returned by calls to a function.
+  Return(String),
+
+  /// A parameter of a function, within the body of that function.
+  Param { function: String, index: usize },
+
+  /// A struct field, wherever it is accessed.
+  Field(String),
+}
+
+/// Arguments of a function that must not receive secret data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SinkSpec {
+  /// The path of the function.
+  pub function: String,
+
+  /// The indices of the arguments that are sinks, or all arguments if omitted.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub args: Option<Vec<usize>>,
+}
+
+impl SinkSpec {
+  /// Returns true if the `index`-th argument is a sink.
+  pub fn includes_arg(&self, index: usize) -> bool {
+    self.args.as_ref().is_none_or(|args| args.contains(&index))
+  }
+}
+
+/// A source or a sink of a config, by its index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SpecIndex {
+  Source(usize),
+  Sink(usize),
+}
+
+/// The contents of a config file, see the [module-level documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IfcConfig {
+  #[serde(default)]
+  pub sources: Vec<SourceSpec>,
+
+  #[serde(default)]
+  pub sinks: Vec<SinkSpec>,
+
+  /// The sources and sinks that matched an item, see [`IfcConfig::unmatched`].
+  #[serde(skip)]
+  matched: RefCell<HashSet<SpecIndex>>,
+}
+
+impl IfcConfig {
+  /// Parses a config from a JSON string.
+  pub fn parse(contents: &str) -> Result<Self> {
+    serde_json::from_str(contents).context("Failed to parse IFC config")
+  }
+
+  /// Reads and parses a config from a JSON file.
+  pub fn load(path: &Path) -> Result<Self> {
+    let contents = fs::read_to_string(path)
+      .with_context(|| format!("Failed to read IFC config from {}", path.display()))?;
+    Self::parse(&contents)
+  }
+
+  /// Returns the sources that satisfy `matches`, and records that they matched.
+  fn matching_sources(&self, matches: impl Fn(&SourceSpec) -> bool) -> Vec<&SourceSpec> {
+    let mut matched = self.matched.borrow_mut();
+    self
+      .sources
+      .iter()
+      .enumerate()
+      .filter(|(_, source)| matches(source))
+      .map(|(index, source)| {
+        matched.insert(SpecIndex::Source(index));
+        source
+      })
+      .collect()
+  }
+
+  /// Returns true if calls to any of `callees` return secret data.
+  pub fn is_source_return(&self, callees: &[String]) -> bool {
+    !self
+      .matching_sources(|source| {
+        matches!(source, SourceSpec::Return(function) if callees.contains(function))
+      })
+      .is_empty()
+  }
+
+  /// Returns the indices of the secret parameters of `function`.
+  pub fn source_params(&self, function: &str) -> impl Iterator<Item = usize> + '_ {
+    self
+      .matching_sources(|source| {
+        matches!(source, SourceSpec::Param { function: param_fn, .. } if param_fn == function)
+      })
+      .into_iter()
+      .filter_map(|source| match source {
+        SourceSpec::Param { index, .. } => Some(*index),
+        _ => None,
+      })
+  }
+
+  /// Returns true if the field `field` is secret.
+  pub fn is_source_field(&self, field: &str) -> bool {
+    !self
+      .matching_sources(
+        |source| matches!(source, SourceSpec::Field(path) if path == field),
+      )
+      .is_empty()
+  }
+
+  /// Returns the sink specs that apply to calls of any of `callees`.
+  pub fn sinks_for(&self, callees: &[String]) -> Vec<&SinkSpec> {
+    let mut matched = self.matched.borrow_mut();
+    self
+      .sinks
+      .iter()
+      .enumerate()
+      .filter(|(_, sink)| callees.contains(&sink.function))
+      .map(|(index, sink)| {
+        matched.insert(SpecIndex::Sink(index));
+        sink
+      })
+      .collect()
+  }
+
+  /// Returns the sources and sinks, as JSON, that have not matched an item since the last
+  /// call, and forgets the matches so that the next crate is checked from scratch.
+  pub fn unmatched(&self) -> Vec<String> {
+    let matched = self.matched.take();
+    let sources = self
+      .sources
+      .iter()
+      .enumerate()
+      .filter(|(index, _)| !matched.contains(&SpecIndex::Source(*index)))
+      .map(|(_, source)| serde_json::to_string(source));
+    let sinks = self
+      .sinks
+      .iter()
+      .enumerate()
+      .filter(|(index, _)| !matched.contains(&SpecIndex::Sink(*index)))
+      .map(|(_, sink)| serde_json::to_string(sink));
+    sources
+      .chain(sinks)
+      .map(|spec| spec.expect("specs are serializable"))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_unmatched() {
+    let config = IfcConfig::parse(
+      r#"{"sources": [{"return": "read_secret"}, {"field": "User::password"}],
+          "sinks": [{"function": "send", "args": [1]}, {"function": "my_crate::send"}]}"#,
+    )
+    .unwrap();
+    assert!(config.is_source_return(&["read_secret".to_string()]));
+    assert!(!config.is_source_field("my_crate::User::password"));
+    assert_eq!(config.sinks_for(&["send".to_string()]).len(), 1);
+    assert_eq!(config.unmatched(), vec![
+      r#"{"field":"User::password"}"#,
+      r#"{"function":"my_crate::send"}"#,
+    ]);
+    assert_eq!(config.unmatched().len(), 4);
+  }
+}
@@ -0,0 +1,123 @@
+//! Security labels and the lattice that orders them.
+//!
+//! There are two kinds of labels. The built-in labels are `Secure` and `Insecure`, where secure
+//! data must never flow into insecure places. User-defined labels are types that implement
+//! `flowistry_ifc_traits::Label`, ordered by their `FlowsTo` impls: data labeled `A` may flow into
+//! places labeled `B` if `A` and `B` are the same, or `A: FlowsTo<B>` holds transitively.
+//! Any ordering can be expressed this way, including product lattices that are only
+//! partially ordered. The two kinds of labels are independent, so user-defined labels are only
+//! compared with each other.
+
+use rustc_data_structures::fx::{FxHashMap as HashMap, FxHashSet as HashSet, FxIndexMap};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{ParamEnv, Ty, TyCtxt, TypeVisitableExt};
+
+use crate::analysis::implements_trait;
+
+/// The label of a source or sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Label {
+  /// Data that implements `Secure`, or a configured source.
+  Secure,
+  /// Data that implements `Insecure`, or a configured sink.
+  Insecure,
+  /// A type that implements `Label`.
+  User(DefId),
+}
+
+/// The user-defined labels of a crate and their order, see the [module-level documentation](self).
+pub(crate) struct Lattice<'tcx> {
+  tcx: TyCtxt<'tcx>,
+  labeled_trait: Option<DefId>,
+  labels: FxIndexMap<DefId, Ty<'tcx>>,
+  flows_to: HashSet<(DefId, DefId)>,
+}
+
+impl<'tcx> Lattice<'tcx> {
+  /// Finds every label type and computes the transitive closure of their `FlowsTo` impls.
+  pub(crate) fn build(tcx: TyCtxt<'tcx>, ifc_items: &HashMap<String, DefId>) -> Self {
+    let mut lattice = Lattice {
+      tcx,
+      labeled_trait: ifc_items.get("Labeled").copied(),
+      labels: FxIndexMap::default(),
+      flows_to: HashSet::default(),
+    };
+    let (Some(label_trait), Some(flows_to_trait)) =
+      (ifc_items.get("Label"), ifc_items.get("FlowsTo"))
+    else {
+      return lattice;
+    };
+
+    lattice.labels = tcx
+      .all_impls(*label_trait)
+      .filter_map(|impl_def_id| {
+        let ty = tcx
+          .impl_trait_ref(impl_def_id)?
+          .instantiate_identity()
+          .self_ty();
+        let adt_def = ty.ty_adt_def()?;
+        (!ty.has_param()).then_some((adt_def.did(), ty))
+      })
+      .collect();
+
+    let param_env = ParamEnv::empty();
+    for (from, from_ty) in &lattice.labels {
+      for (to, to_ty) in &lattice.labels {
+        if implements_trait(tcx, param_env, *from_ty, *flows_to_trait, &[*to_ty]) {
+          lattice.flows_to.insert((*from, *to));
+        }
+      }
+    }
+
+    // Warshall's algorithm for the transitive closure.
+    let labels = lattice.labels.keys().copied().collect::<Vec<_>>();
+    for mid in &labels {
+      for from in &labels {
+        for to in &labels {
+          if lattice.flows_to.contains(&(*from, *mid))
+            && lattice.flows_to.contains(&(*mid, *to))
+          {
+            lattice.flows_to.insert((*from, *to));
+          }
+        }
+      }
+    }
+
+    lattice
+  }
+
+  /// Returns the user-defined labels of a type, i.e. each `L` such that the type implements `Labeled<L>`.
+  pub(crate) fn labels_of(&self, param_env: ParamEnv<'tcx>, ty: Ty<'tcx>) -> Vec<Label> {
+    let Some(labeled_trait) = self.labeled_trait else {
+      return Vec::new();
+    };
+    self
+      .labels
+      .iter()
+      .filter(|(_, label_ty)| {
+        implements_trait(self.tcx, param_env, ty, labeled_trait, &[**label_ty])
+      })
+      .map(|(label, _)| Label::User(*label))
+      .collect()
+  }
+
+  /// Returns true if data labeled `from` may flow into a place labeled `to`.
+  pub(crate) fn flows_to(&self, from: Label, to: Label) -> bool {
+    match (from, to) {
+      (Label::Secure, Label::Insecure) => false,
+      (Label::User(from), Label::User(to)) => {
+        from == to || self.flows_to.contains(&(from, to))
+      }
+      _ => true,
+    }
+  }
+
+  /// Returns a description of a label to include in a report, e.g. `secret` or `` `Confidential` ``.
+  pub(crate) fn describe(&self, label: Label) -> String {
+    match label {
+      Label::Secure => "secret".to_string(),
+      Label::Insecure => "insecure".to_string(),
+      Label::User(def_id) => format!("`{}`", self.tcx.def_path_str(def_id)),
+    }
+  }
+}
@@ -1,10 +1,15 @@
-#![feature(rustc_private)]
+#![feature(rustc_private, box_patterns)]
 
+extern crate either;
+extern crate rustc_abi;
 extern crate rustc_data_structures;
 extern crate rustc_driver;
 extern crate rustc_hir;
+extern crate rustc_index;
 extern crate rustc_infer;
 extern crate rustc_interface;
+extern crate rustc_lint;
+extern crate rustc_lint_defs;
 extern crate rustc_middle;
 extern crate rustc_mir_dataflow;
 extern crate rustc_span;
@@ -12,23 +17,133 @@ extern crate rustc_trait_selection;
 extern crate rustc_traits;
 
 mod analysis;
+pub mod baseline;
+mod class;
+pub mod config;
+mod label;
+pub mod report;
+#[cfg(feature = "test")]
+pub mod test_utils;
 
-use std::{borrow::Cow, io::Write};
+use std::{borrow::Cow, env, fs, io::Write, path::PathBuf, process::exit, str::FromStr};
 
-use analysis::IssueFound;
-use flowistry::infoflow;
-use rustc_hir::{
-  BodyId,
-  intravisit::{self, Visitor},
-};
-use rustc_middle::{hir::nested_filter::OnlyBodies, ty::TyCtxt};
+use baseline::{Baseline, DEFAULT_BASELINE_FILE};
+use clap::Parser;
+use config::{DEFAULT_CONFIG_FILE, IfcConfig};
+use flowistry::extensions::{ContextMode, EvalMode, MutabilityMode, PointerMode};
+use glob::Pattern;
+use report::OutputFormat;
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::ty::TyCtxt;
 use rustc_plugin::{CrateFilter, RustcPlugin, RustcPluginArgs};
 use rustc_utils::mir::borrowck_facts;
+use serde::{Deserialize, Serialize};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+/// Whether a class of flows is reported as an error, a warning, or not at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowPolicy {
+  Deny,
+  Warn,
+  Allow,
+}
+
+impl FromStr for FlowPolicy {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "deny" => Ok(Self::Deny),
+      "warn" => Ok(Self::Warn),
+      "allow" => Ok(Self::Allow),
+      _ => Err(format!("Could not parse: {s}")),
+    }
+  }
+}
+
+impl FlowPolicy {
+  /// Returns the rustc flag that sets a lint to this level.
+  fn lint_flag(self, lint_name: &str) -> String {
+    let flag = match self {
+      FlowPolicy::Deny => "-D",
+      FlowPolicy::Warn => "-W",
+      FlowPolicy::Allow => "-A",
+    };
+    format!("{flag}{lint_name}")
+  }
+}
+
+#[derive(Parser, Serialize, Deserialize)]
+pub struct IfcPluginArgs {
+  /// Only check these packages of the workspace, defaults to every package.
+  #[clap(long, short)]
+  package: Vec<String>,
+
+  /// Only check functions whose path matches this glob, e.g. `auth::*`. Paths are printed
+  /// as in findings, without the crate name. Calls to other functions are treated like calls
+  /// to external functions.
+  #[clap(long)]
+  function: Option<String>,
+
+  #[clap(long)]
+  context_mode: Option<ContextMode>,
+  #[clap(long)]
+  pointer_mode: Option<PointerMode>,
+  #[clap(long)]
+  mutability_mode: Option<MutabilityMode>,
+
+  /// Exit with an error if there are any findings, including warnings.
+  #[clap(long)]
+  fail_on_findings: bool,
+
+  /// JSON file of sources and sinks, defaults to `flowistry-ifc.json`
+  /// in the workspace root if it exists.
+  #[clap(long)]
+  config: Option<PathBuf>,
+
+  /// Also write findings as `json` or `sarif`, in addition to diagnostics.
+  #[clap(long, default_value = "text")]
+  format: OutputFormat,
+
+  /// File to write JSON or SARIF findings to, defaults to stdout. When checking
+  /// several crates, `{crate}` in the path is replaced by the crate name.
+  #[clap(long)]
+  output: Option<PathBuf>,
+
+  /// JSON file of accepted findings, which are not reported, defaults to
+  /// `flowistry-ifc-baseline.json` in the workspace root.
+  #[clap(long)]
+  baseline: Option<PathBuf>,
+
+  /// Write the current findings to the baseline file instead of reporting them.
+  #[clap(long, conflicts_with = "no_baseline")]
+  update_baseline: bool,
+
+  /// Report every finding, including those in the baseline file.
+  #[clap(long)]
+  no_baseline: bool,
+
+  /// How to report explicit flows (`deny`, `warn` or `allow`), defaults to `deny`.
+  #[clap(long)]
+  explicit_flows: Option<FlowPolicy>,
+
+  /// How to report implicit flows through branches, defaults to `warn`.
+  #[clap(long)]
+  implicit_flows: Option<FlowPolicy>,
+
+  /// How to report flows through loops and early returns, defaults to `warn`.
+  #[clap(long)]
+  termination_flows: Option<FlowPolicy>,
+
+  /// How to report branch conditions and array indices that depend on secret data,
+  /// defaults to `allow`. Use `deny` to check that code runs in constant time.
+  #[clap(long)]
+  timing_flows: Option<FlowPolicy>,
+}
+
 pub struct IfcPlugin;
 
 impl RustcPlugin for IfcPlugin {
-  type Args = ();
+  type Args = IfcPluginArgs;
 
   fn driver_name(&self) -> Cow<'static, str> {
     "ifc-driver".into()
@@ -40,47 +155,137 @@ impl RustcPlugin for IfcPlugin {
 
   fn args(&self, _target_dir: &rustc_plugin::Utf8Path) -> RustcPluginArgs<Self::Args> {
     RustcPluginArgs {
-      args: (),
+      args: IfcPluginArgs::parse_from(env::args().skip(1)),
       filter: CrateFilter::OnlyWorkspace,
     }
   }
 
   fn run(
     self,
-    compiler_args: Vec<String>,
-    _plugin_args: Self::Args,
+    mut compiler_args: Vec<String>,
+    plugin_args: Self::Args,
   ) -> rustc_interface::interface::Result<()> {
-    rustc_driver::run_compiler(&compiler_args, &mut Callbacks);
+    // Cargo checks every workspace member, so other packages are compiled normally.
+    if !plugin_args.package.is_empty() {
+      let package = env::var("CARGO_PKG_NAME").unwrap_or_default();
+      if !plugin_args.package.contains(&package) {
+        rustc_driver::run_compiler(&compiler_args, &mut DefaultCallbacks);
+        return Ok(());
+      }
+    }
+
+    let function = plugin_args
+      .function
+      .as_deref()
+      .map(Pattern::new)
+      .transpose()
+      .unwrap_or_else(|error| {
+        eprintln!("Invalid --function pattern: {error}");
+        exit(1);
+      });
+
+    let config_path = plugin_args.config.or_else(|| {
+      let default_path = PathBuf::from(DEFAULT_CONFIG_FILE);
+      default_path.exists().then_some(default_path)
+    });
+    let config = match config_path {
+      Some(path) => match IfcConfig::load(&path) {
+        Ok(config) => config,
+        Err(error) => {
+          eprintln!("{error:?}");
+          exit(1);
+        }
+      },
+      None => IfcConfig::default(),
+    };
+
+    // Lets crates suppress findings with `#[cfg_attr(flowistry_ifc, allow(insecure_flow))]`,
+    // and keeps the `flowistry_ifc::secret` attributes generated by `#[ifc::secret]`.
+    compiler_args.extend([
+      "--cfg=flowistry_ifc".to_string(),
+      "--check-cfg=cfg(flowistry_ifc)".to_string(),
+      "-Zcrate-attr=feature(register_tool)".to_string(),
+      "-Zcrate-attr=register_tool(flowistry_ifc)".to_string(),
+    ]);
+    for (policy, lint) in [
+      (plugin_args.explicit_flows, analysis::INSECURE_FLOW),
+      (plugin_args.implicit_flows, analysis::IMPLICIT_FLOW),
+      (plugin_args.termination_flows, analysis::TERMINATION_FLOW),
+      (plugin_args.timing_flows, analysis::TIMING_FLOW),
+    ] {
+      if let Some(policy) = policy {
+        compiler_args.push(policy.lint_flag(&lint.name_lower()));
+      }
+    }
+
+    let baseline_path = plugin_args
+      .baseline
+      .unwrap_or_else(|| PathBuf::from(DEFAULT_BASELINE_FILE));
+    let baseline = if plugin_args.no_baseline || plugin_args.update_baseline {
+      Baseline::default()
+    } else {
+      match Baseline::load(&baseline_path) {
+        Ok(baseline) => baseline,
+        Err(error) => {
+          eprintln!("{error:?}");
+          exit(1);
+        }
+      }
+    };
+
+    let eval_mode = EvalMode {
+      context_mode: plugin_args.context_mode.unwrap_or(ContextMode::SigOnly),
+      mutability_mode: plugin_args
+        .mutability_mode
+        .unwrap_or(MutabilityMode::DistinguishMut),
+      pointer_mode: plugin_args.pointer_mode.unwrap_or(PointerMode::Precise),
+      ..Default::default()
+    };
+
+    rustc_driver::run_compiler(&compiler_args, &mut Callbacks {
+      config,
+      function,
+      eval_mode,
+      format: plugin_args.format,
+      output: plugin_args.output,
+      baseline,
+      update_baseline: plugin_args.update_baseline.then_some(baseline_path),
+      fail_on_findings: plugin_args.fail_on_findings,
+    });
     Ok(())
   }
 }
 
-pub struct IfcVisitor<'tcx> {
-  tcx: TyCtxt<'tcx>,
-  issue_found: IssueFound,
-}
+struct DefaultCallbacks;
+impl rustc_driver::Callbacks for DefaultCallbacks {}
 
-impl<'tcx> Visitor<'tcx> for IfcVisitor<'tcx> {
-  type NestedFilter = OnlyBodies;
+pub struct Callbacks {
+  config: IfcConfig,
 
-  fn visit_nested_body(&mut self, body_id: BodyId) {
-    intravisit::walk_body(self, self.tcx.hir_body(body_id));
+  /// Only functions whose path matches the pattern are checked, if any.
+  function: Option<Pattern>,
+  eval_mode: EvalMode,
+  format: OutputFormat,
+  output: Option<PathBuf>,
+  baseline: Baseline,
 
-    let tcx = self.tcx;
-    let local_def_id = tcx.hir_body_owner_def_id(body_id);
-    let body_with_facts = borrowck_facts::get_body_with_borrowck_facts(tcx, local_def_id);
-    let flow = &infoflow::compute_flow(tcx, body_id, body_with_facts);
-    if let IssueFound::Yes = analysis::analyze(&body_id, flow).unwrap() {
-      self.issue_found = IssueFound::Yes;
-    }
-  }
+  /// The baseline file to write findings to, if updating the baseline.
+  update_baseline: Option<PathBuf>,
+  fail_on_findings: bool,
 }
 
-pub struct Callbacks;
 impl rustc_driver::Callbacks for Callbacks {
   fn config(&mut self, config: &mut rustc_interface::Config) {
     borrowck_facts::enable_mir_simplification();
     config.override_queries = Some(borrowck_facts::override_queries);
+    config.register_lints = Some(Box::new(|_sess, lint_store| {
+      lint_store.register_lints(&[
+        analysis::INSECURE_FLOW,
+        analysis::IMPLICIT_FLOW,
+        analysis::TERMINATION_FLOW,
+        analysis::TIMING_FLOW,
+      ]);
+    }));
   }
 
   fn after_analysis(
@@ -88,13 +293,76 @@ impl rustc_driver::Callbacks for Callbacks {
     _compiler: &rustc_interface::interface::Compiler,
     tcx: TyCtxt,
   ) -> rustc_driver::Compilation {
-    let mut visitor = IfcVisitor {
-      tcx,
-      issue_found: IssueFound::No,
+    let flows =
+      analysis::check_crate(tcx, &self.config, self.function.as_ref(), self.eval_mode);
+    let crate_name = tcx.crate_name(LOCAL_CRATE).to_string();
+
+    if let Some(path) = &self.update_baseline {
+      let findings = flows
+        .iter()
+        .filter(|flow| !flow.is_suppressed(tcx))
+        .map(|flow| flow.finding.clone())
+        .collect::<Vec<_>>();
+      match Baseline::update(path, &crate_name, &findings) {
+        Ok(()) => eprintln!(
+          "Wrote {} finding(s) for {crate_name} to {}",
+          findings.len(),
+          path.display()
+        ),
+        Err(error) => {
+          tcx
+            .dcx()
+            .err(format!("Failed to update IFC baseline: {error:?}"));
+        }
+      }
+      return rustc_driver::Compilation::Stop;
+    }
+
+    // Suppressed flows are still emitted, so that `#[expect(insecure_flow)]` is fulfilled.
+    let mut findings = Vec::new();
+    for flow in flows {
+      if flow.is_suppressed(tcx) {
+        flow.emit(tcx);
+      } else if !self.baseline.contains(&flow.finding) {
+        flow.emit(tcx);
+        findings.push(flow.finding);
+      }
+    }
+
+    let report = match self.format {
+      OutputFormat::Text => None,
+      OutputFormat::Json => Some(report::to_json(&findings)),
+      OutputFormat::Sarif => Some(report::to_sarif(&findings)),
     };
-    tcx.hir_visit_all_item_likes_in_crate(&mut visitor);
+    match report {
+      Some(Ok(report)) => match &self.output {
+        Some(path) => {
+          let path = path.to_string_lossy().replace("{crate}", &crate_name);
+          if let Err(error) = fs::write(&path, report) {
+            tcx
+              .dcx()
+              .err(format!("Failed to write IFC report to {path}: {error}"));
+          }
+        }
+        None => println!("{report}"),
+      },
+      Some(Err(error)) => {
+        tcx
+          .dcx()
+          .err(format!("Failed to serialize IFC report: {error}"));
+      }
+      None => {}
+    }
+
+    // Findings that are errors already fail the build.
+    if self.fail_on_findings && !findings.is_empty() && tcx.dcx().has_errors().is_none() {
+      tcx.dcx().err(format!(
+        "found {} IFC finding(s) in {crate_name}",
+        findings.len()
+      ));
+    }
 
-    if let IssueFound::No = visitor.issue_found {
+    if findings.is_empty() {
       let mut stdout = StandardStream::stderr(ColorChoice::Auto);
       let mut green_spec = ColorSpec::new();
       green_spec.set_fg(Some(Color::Green));
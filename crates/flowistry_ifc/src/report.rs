@@ -0,0 +1,359 @@
+//! Machine-readable reports of IFC findings.
+//!
+//! Findings are always emitted as `insecure_flow` diagnostics. With `--format json` or
+//! `--format sarif`, `cargo ifc` also writes them as a JSON array of [`Finding`]s or as a
+//! [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) log,
+//! which code scanning dashboards can import.
+
+use std::str::FromStr;
+
+use anyhow::Result;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// The format of the findings written by `cargo ifc`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputFormat {
+  /// Only diagnostics.
+  #[default]
+  Text,
+  Json,
+  Sarif,
+}
+
+impl FromStr for OutputFormat {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "text" => Ok(Self::Text),
+      "json" => Ok(Self::Json),
+      "sarif" => Ok(Self::Sarif),
+      _ => Err(format!("Could not parse: {s}")),
+    }
+  }
+}
+
+/// A range of source code. Lines and columns start at 1, and the end column is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceRange {
+  pub file: String,
+  pub start_line: usize,
+  pub start_column: usize,
+  pub end_line: usize,
+  pub end_column: usize,
+  pub snippet: String,
+}
+
+impl SourceRange {
+  pub(crate) fn from_span(tcx: TyCtxt, span: Span) -> Self {
+    let source_map = tcx.sess.source_map();
+    let start = source_map.lookup_char_pos(span.lo());
+    let end = source_map.lookup_char_pos(span.hi());
+    SourceRange {
+      file: source_map
+        .filename_for_diagnostics(&start.file.name)
+        .to_string(),
+      start_line: start.line,
+      start_column: start.col.0 + 1,
+      end_line: end.line,
+      end_column: end.col.0 + 1,
+      snippet: source_map.span_to_snippet(span).unwrap_or_default(),
+    }
+  }
+
+  fn to_sarif(&self) -> Value {
+    json!({
+      "physicalLocation": {
+        "artifactLocation": { "uri": self.file },
+        "region": {
+          "startLine": self.start_line,
+          "startColumn": self.start_column,
+          "endLine": self.end_line,
+          "endColumn": self.end_column,
+          "snippet": { "text": self.snippet },
+        },
+      },
+    })
+  }
+}
+
+/// How a sink depends on a source, see [`crate::class`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowClass {
+  Explicit,
+  Implicit,
+  Termination,
+
+  /// A branch condition or an array index depends on the source, which is only reported
+  /// for constant-time code with `--timing-flows`.
+  Timing,
+}
+
+/// An insecure flow from a source to a sink.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+  /// Identifies the finding across runs, see [`set_fingerprints`].
+  pub fingerprint: String,
+
+  /// The name of the lint that reports the finding.
+  pub rule_id: String,
+  pub class: FlowClass,
+
+  /// The level of the lint, `error` or `warning`.
+  pub level: String,
+  pub message: String,
+  pub crate_name: String,
+
+  /// The path of the function that contains the source and the sink.
+  pub function: String,
+  pub source: SourceRange,
+  pub sink: SourceRange,
+
+  /// Every step of the flow, from the original source (which may be in a callee)
+  /// to the final sink (which may also be in a callee).
+  pub path: Vec<SourceRange>,
+}
+
+impl Finding {
+  fn fingerprint_key(&self) -> String {
+    let normalize =
+      |snippet: &str| snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    [
+      self.rule_id.as_str(),
+      &self.crate_name,
+      &self.function,
+      &normalize(&self.source.snippet),
+      &normalize(&self.sink.snippet),
+    ]
+    .join("\0")
+  }
+}
+
+/// Sets the fingerprints of the findings of a function.
+///
+/// The fingerprint is a hash of the rule, the crate, the function, and the source code of the
+/// source and the sink, ignoring whitespace. It does not include line numbers, so it stays the
+/// same when unrelated code is added or removed. Findings with the same source code, e.g. two
+/// identical calls, are told apart by their index among each other in source order.
+pub fn set_fingerprints<'a>(findings: impl IntoIterator<Item = &'a mut Finding>) {
+  let position = |range: &SourceRange| (range.start_line, range.start_column);
+  let mut findings = findings
+    .into_iter()
+    .map(|finding| (finding.fingerprint_key(), finding))
+    .collect::<Vec<_>>();
+  findings.sort_by(|(key_a, a), (key_b, b)| {
+    (key_a, position(&a.sink), position(&a.source)).cmp(&(
+      key_b,
+      position(&b.sink),
+      position(&b.source),
+    ))
+  });
+  let mut previous = None;
+  let mut occurrence = 0;
+  for (key, finding) in findings {
+    occurrence = if previous.as_ref() == Some(&key) {
+      occurrence + 1
+    } else {
+      0
+    };
+    let hash = fnv1a(format!("{key}\0{occurrence}").as_bytes());
+    finding.fingerprint = format!("{hash:016x}");
+    previous = Some(key);
+  }
+}
+
+/// The 64-bit FNV-1a hash, which (unlike the standard library's hashers) is stable
+/// across platforms and compiler versions.
+fn fnv1a(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+    (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+  })
+}
+
+/// Serializes findings as a JSON array.
+pub fn to_json(findings: &[Finding]) -> Result<String> {
+  Ok(serde_json::to_string_pretty(findings)?)
+}
+
+/// Serializes findings as a SARIF log with a single run.
+pub fn to_sarif(findings: &[Finding]) -> Result<String> {
+  let mut rule_ids = findings
+    .iter()
+    .map(|finding| finding.rule_id.as_str())
+    .collect::<Vec<_>>();
+  rule_ids.sort();
+  rule_ids.dedup();
+  let rules = rule_ids
+    .iter()
+    .map(|id| json!({ "id": id, "shortDescription": { "text": id.replace('_', " ") } }))
+    .collect::<Vec<_>>();
+
+  let results = findings
+    .iter()
+    .map(|finding| {
+      let mut source = finding.source.to_sarif();
+      source["id"] = json!(0);
+      source["message"] = json!({ "text": "source" });
+      let thread_flow = finding
+        .path
+        .iter()
+        .map(|range| json!({ "location": range.to_sarif() }))
+        .collect::<Vec<_>>();
+      json!({
+        "ruleId": finding.rule_id,
+        "level": finding.level,
+        "message": { "text": finding.message },
+        "locations": [finding.sink.to_sarif()],
+        "relatedLocations": [source],
+        "codeFlows": [{ "threadFlows": [{ "locations": thread_flow }] }],
+        "partialFingerprints": { "flowistryIfc/v1": finding.fingerprint },
+        "properties": {
+          "class": finding.class,
+          "crate": finding.crate_name,
+          "function": finding.function,
+        },
+      })
+    })
+    .collect::<Vec<_>>();
+
+  let log = json!({
+    "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+    "version": "2.1.0",
+    "runs": [{
+      "tool": {
+        "driver": {
+          "name": "flowistry-ifc",
+          "version": env!("CARGO_PKG_VERSION"),
+          "rules": rules,
+        },
+      },
+      "results": results,
+    }],
+  });
+  Ok(serde_json::to_string_pretty(&log)?)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+  use super::*;
+
+  fn range(line: usize, snippet: &str) -> SourceRange {
+    SourceRange {
+      file: "src/lib.rs".to_string(),
+      start_line: line,
+      start_column: 3,
+      end_line: line,
+      end_column: 3 + snippet.len(),
+      snippet: snippet.to_string(),
+    }
+  }
+
+  /// Returns a finding in `function` from a source on line 1 to a sink on `sink_line`.
+  pub(crate) fn finding(function: &str, sink_line: usize, sink: &str) -> Finding {
+    let source = range(1, "read_password()");
+    let sink = range(sink_line, sink);
+    Finding {
+      fingerprint: "0123456789abcdef".to_string(),
+      rule_id: "insecure_flow".to_string(),
+      class: FlowClass::Explicit,
+      level: "error".to_string(),
+      message: "insecure flow from secret data to insecure data".to_string(),
+      crate_name: "example".to_string(),
+      function: function.to_string(),
+      path: vec![source.clone(), sink.clone()],
+      source,
+      sink,
+    }
+  }
+
+  #[test]
+  fn test_sarif() {
+    let findings = [finding("example::login", 2, "log(password)")];
+    let log: Value = serde_json::from_str(&to_sarif(&findings).unwrap()).unwrap();
+    assert_eq!(log["version"], "2.1.0");
+
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "flowistry-ifc");
+    assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "insecure_flow");
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    let result = &results[0];
+    assert_eq!(result["ruleId"], "insecure_flow");
+    assert_eq!(result["level"], "error");
+    assert_eq!(
+      result["message"]["text"],
+      "insecure flow from secret data to insecure data"
+    );
+
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "src/lib.rs");
+    assert_eq!(location["region"]["startLine"], 2);
+    assert_eq!(location["region"]["startColumn"], 3);
+    assert_eq!(location["region"]["snippet"]["text"], "log(password)");
+
+    let flow = result["codeFlows"][0]["threadFlows"][0]["locations"]
+      .as_array()
+      .unwrap();
+    let lines = flow
+      .iter()
+      .map(|step| step["location"]["physicalLocation"]["region"]["startLine"].clone())
+      .collect::<Vec<_>>();
+    assert_eq!(lines, [1, 2]);
+
+    assert_eq!(
+      result["partialFingerprints"]["flowistryIfc/v1"],
+      "0123456789abcdef"
+    );
+  }
+
+  #[test]
+  fn test_fingerprints() {
+    let fingerprints = |findings: &mut [Finding]| {
+      set_fingerprints(findings.iter_mut());
+      findings
+        .iter()
+        .map(|finding| finding.fingerprint.clone())
+        .collect::<Vec<_>>()
+    };
+
+    // Identical sinks are told apart by their order.
+    let mut findings = [
+      finding("example::login", 2, "log(password)"),
+      finding("example::login", 3, "log(password)"),
+      finding("example::login", 4, "send(host, password)"),
+    ];
+    let original = fingerprints(&mut findings);
+    assert_ne!(original[0], original[1]);
+    assert_ne!(original[0], original[2]);
+
+    // Moving the function or reformatting the sinks does not change the fingerprints,
+    // regardless of the order of the findings.
+    let mut moved = [
+      finding("example::login", 14, "send(host,\n    password)"),
+      finding("example::login", 13, "log(password)"),
+      finding("example::login", 12, "log(password)"),
+    ];
+    let moved = fingerprints(&mut moved);
+    assert_eq!(moved, [
+      original[2].clone(),
+      original[1].clone(),
+      original[0].clone()
+    ]);
+
+    // Other functions have other fingerprints.
+    let mut other = [finding("example::logout", 2, "log(password)")];
+    assert_ne!(fingerprints(&mut other)[0], original[0]);
+  }
+
+  #[test]
+  fn test_json() {
+    let findings = vec![finding("example::login", 2, "log(password)")];
+    let parsed: Vec<Finding> =
+      serde_json::from_str(&to_json(&findings).unwrap()).unwrap();
+    assert_eq!(parsed, findings);
+  }
+}
@@ -0,0 +1,260 @@
+//! Running the IFC checker on test programs.
+//!
+//! Each test program marks the lines where findings are expected with a comment, e.g.
+//!
+//! ```ignore
+//! insecure_print!("{}", password); // ~ERROR flows into insecure data
+//! if password.0.is_empty() { insecure_print!("empty"); } // ~WARN depends on secure
+//! ```
+//!
+//! `~ERROR` expects a finding of a lint that is denied by default and `~WARN` expects a finding
+//! of a lint that warns by default, on the line of the sink. The text after the marker, if any,
+//! must be part of the finding's message. Every finding must be marked, except for lints that
+//! are allowed like `timing_flow` by default. Test programs can use `flowistry_ifc_traits`,
+//! which must be a dependency of the test crate.
+//!
+//! A header on the first line sets lint levels like the rustc flags `-D`, `-W` and `-A`, and
+//! provides an [`IfcConfig`] as JSON after `config`, e.g. `/* -Dtiming_flow config {...} */`.
+
+#![allow(missing_docs)]
+
+use std::{
+  collections::HashMap,
+  env, fmt, fs, panic,
+  path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use flowistry::extensions::{ContextMode, EvalMode, MutabilityMode, PointerMode};
+use log::info;
+use rustc_lint_defs::Level;
+use rustc_utils::{mir::borrowck_facts, test_utils::CompileBuilder};
+
+use crate::{analysis, config::IfcConfig};
+
+const ONLY: Option<&'static str> = option_env!("ONLY");
+const EXIT: bool = option_env!("EXIT").is_some();
+
+/// A finding that is expected or reported on a line of a test program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Marker {
+  line: usize,
+  kind: &'static str,
+  message: String,
+}
+
+impl fmt::Display for Marker {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "line {}: ~{} {}", self.line, self.kind, self.message)
+  }
+}
+
+fn parse_markers(input: &str) -> Result<Vec<Marker>> {
+  let mut markers = Vec::new();
+  for (index, line) in input.lines().enumerate() {
+    for marker in line.split("// ~").skip(1) {
+      let (kind, message) = marker.split_once(' ').unwrap_or((marker, ""));
+      let kind = match kind.trim() {
+        "ERROR" => "ERROR",
+        "WARN" => "WARN",
+        kind => anyhow::bail!("Unknown marker `~{kind}` on line {}", index + 1),
+      };
+      markers.push(Marker {
+        line: index + 1,
+        kind,
+        message: message.trim().to_string(),
+      });
+    }
+  }
+  Ok(markers)
+}
+
+/// The settings of a test program, given by its first line.
+#[derive(Default)]
+struct Header {
+  config: IfcConfig,
+
+  /// Lint levels that override the lints' defaults, by lint name.
+  levels: HashMap<String, Level>,
+}
+
+fn parse_header(header: &str) -> Result<Header> {
+  let Some(header) = header.trim_end().strip_prefix("/*") else {
+    return Ok(Header::default());
+  };
+  let (flags, config) = match header.split_once("config") {
+    Some((flags, json)) => (flags, IfcConfig::parse(json.trim_end_matches("*/"))?),
+    None => (header.trim_end_matches("*/"), IfcConfig::default()),
+  };
+
+  let mut levels = HashMap::new();
+  for flag in flags.split_whitespace() {
+    let (level, lint) = flag.split_at_checked(2).unwrap_or((flag, ""));
+    let level = match level {
+      "-D" => Level::Deny,
+      "-W" => Level::Warn,
+      "-A" => Level::Allow,
+      _ => anyhow::bail!("Unknown flag `{flag}` in the header"),
+    };
+    levels.insert(lint.to_string(), level);
+  }
+
+  Ok(Header { config, levels })
+}
+
+/// Returns the arguments that make `flowistry_ifc_traits` available to test programs, using
+/// the most recently built library next to the test executable.
+fn traits_args() -> Result<Vec<String>> {
+  let exe = env::current_exe()?;
+  let deps_dir = exe
+    .parent()
+    .context("Test executable has no parent directory")?;
+  let traits_lib = fs::read_dir(deps_dir)?
+    .filter_map(|entry| entry.ok())
+    .map(|entry| entry.path())
+    .filter(|path| {
+      let name = path.file_name().unwrap().to_string_lossy();
+      name.starts_with("libflowistry_ifc_traits-") && name.ends_with(".rlib")
+    })
+    .max_by_key(|path: &PathBuf| path.metadata().and_then(|meta| meta.modified()).ok())
+    .context("Could not find flowistry_ifc_traits, is it a dev-dependency?")?;
+  Ok(vec![
+    "--extern".to_string(),
+    format!("flowistry_ifc_traits={}", traits_lib.display()),
+    "-L".to_string(),
+    format!("dependency={}", deps_dir.display()),
+  ])
+}
+
+/// Checks a test program and compares its findings to the markers in the program.
+pub fn test_ifc_output(path: &Path) {
+  let inner = || -> Result<()> {
+    info!("Testing {}", path.file_name().unwrap().to_string_lossy());
+    let input = fs::read_to_string(path)?;
+    let expected = parse_markers(&input)?;
+    let Header { config, levels } =
+      parse_header(input.lines().next().unwrap_or_default())?;
+
+    let mut args = traits_args()?;
+    args.extend([
+      "--cfg=flowistry_ifc".to_string(),
+      "-Zcrate-attr=feature(register_tool)".to_string(),
+      "-Zcrate-attr=register_tool(flowistry_ifc)".to_string(),
+    ]);
+
+    borrowck_facts::enable_mir_simplification();
+    let mut actual = Vec::new();
+    CompileBuilder::new(input)
+      .with_args(args)
+      .compile(|result| {
+        // The config records which entries match, so it is moved to the compiler's thread.
+        let config = config;
+        let tcx = result.tcx;
+        let eval_mode = EvalMode {
+          context_mode: ContextMode::SigOnly,
+          mutability_mode: MutabilityMode::DistinguishMut,
+          pointer_mode: PointerMode::Precise,
+          ..Default::default()
+        };
+        let flows = analysis::check_crate(tcx, &config, None, eval_mode);
+        actual = flows
+          .into_iter()
+          .filter_map(|flow| {
+            // The test programs are compiled with `--allow warnings`, so lint levels are
+            // taken from the header or the lints' defaults.
+            let lint = analysis::class_lint(flow.finding.class);
+            let level = levels
+              .get(&lint.name_lower())
+              .copied()
+              .unwrap_or(lint.default_level);
+            let kind = match level {
+              Level::Deny => "ERROR",
+              Level::Warn => "WARN",
+              _ => return None,
+            };
+            Some(Marker {
+              line: flow.finding.sink.start_line,
+              kind,
+              message: flow.finding.message,
+            })
+          })
+          .collect();
+      });
+
+    let mut unexpected = actual;
+    let mut missing = Vec::new();
+    for marker in expected {
+      let matching = unexpected.iter().position(|finding| {
+        finding.line == marker.line
+          && finding.kind == marker.kind
+          && finding.message.contains(&marker.message)
+      });
+      match matching {
+        Some(index) => {
+          unexpected.remove(index);
+        }
+        None => missing.push(marker),
+      }
+    }
+
+    if !missing.is_empty() || !unexpected.is_empty() {
+      let list = |markers: &[Marker]| {
+        markers
+          .iter()
+          .map(|marker| format!("  {marker}"))
+          .collect::<Vec<_>>()
+          .join("\n")
+      };
+      anyhow::bail!(
+        "Missing findings:\n{}\nUnexpected findings:\n{}",
+        list(&missing),
+        list(&unexpected)
+      );
+    }
+
+    Ok(())
+  };
+
+  inner().unwrap();
+}
+
+/// Runs `test_fn` on every file in `tests/<dir>`. Set `ONLY` to run the tests whose file name
+/// contains it, and `EXIT` to stop at the first failure.
+pub fn run_tests(dir: impl AsRef<Path>, test_fn: impl Fn(&Path) + panic::RefUnwindSafe) {
+  let main = || -> Result<()> {
+    let test_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+      .join("tests")
+      .join(dir.as_ref());
+    let mut tests = fs::read_dir(test_dir)?
+      .map(|entry| entry.map(|entry| entry.path()))
+      .collect::<Result<Vec<_>, _>>()?;
+    tests.sort();
+    let mut failed = false;
+    for test in tests {
+      let test_name = test.file_name().unwrap().to_str().unwrap();
+      if let Some(only) = ONLY
+        && !test_name.contains(only)
+      {
+        continue;
+      }
+
+      let result = panic::catch_unwind(|| test_fn(&test));
+      if let Err(e) = result {
+        if EXIT {
+          panic!("{test_name}:\n{e:?}");
+        } else {
+          failed = true;
+          eprintln!("\n\n{test_name}:\n{e:?}\n\n");
+        }
+      }
+    }
+
+    if failed {
+      panic!("Tests failed.")
+    }
+
+    Ok(())
+  };
+
+  main().unwrap();
+}
@@ -0,0 +1,161 @@
+//! Runs the IFC driver on a test program with the options of `cargo ifc`.
+
+#![feature(rustc_private)]
+
+use std::{env, fs, process::Command};
+
+use clap::Parser;
+use flowistry_ifc::{IfcPluginArgs, report::Finding};
+
+const PROGRAM: &str = r#"
+fn read_secret() -> u32 {
+  42
+}
+
+fn send(_value: u32) {}
+
+pub fn report() {
+  send(read_secret());
+}
+
+pub mod auth {
+  pub fn login() {
+    let attempts = 1;
+    if super::read_secret() == 0 {
+      super::send(attempts);
+    }
+  }
+}
+"#;
+
+// Paths of the crate's own items do not include the crate name, so the second source is
+// mistyped and matches nothing.
+const CONFIG: &str = r#"{
+  "sources": [{"return": "read_secret"}, {"return": "cli_test::read_secret"}],
+  "sinks": [{"function": "send"}]
+}"#;
+
+struct Output {
+  success: bool,
+  functions: Vec<String>,
+  stderr: String,
+}
+
+/// Checks the test program with the driver, as `cargo ifc <args>` would for package `package`.
+fn run_driver(test_name: &str, package: &str, args: &[&str]) -> Output {
+  let dir = env::temp_dir().join(format!(
+    "flowistry-ifc-cli-{test_name}-{}",
+    std::process::id()
+  ));
+  fs::create_dir_all(&dir).unwrap();
+  let source = dir.join("lib.rs");
+  fs::write(&source, PROGRAM).unwrap();
+  let config = dir.join("config.json");
+  fs::write(&config, CONFIG).unwrap();
+
+  let config = config.to_string_lossy();
+  let plugin_args = IfcPluginArgs::parse_from(
+    [
+      "cargo-ifc",
+      "--config",
+      &config,
+      "--no-baseline",
+      "--format",
+      "json",
+    ]
+    .into_iter()
+    .chain(args.iter().copied()),
+  );
+  let output = Command::new(env!("CARGO_BIN_EXE_ifc-driver"))
+    .args([
+      "--crate-type",
+      "lib",
+      "--crate-name",
+      "cli_test",
+      "--edition",
+      "2024",
+    ])
+    .arg(&source)
+    .arg("--out-dir")
+    .arg(&dir)
+    .current_dir(&dir)
+    .env("CARGO_PRIMARY_PACKAGE", "1")
+    .env("CARGO_PKG_NAME", package)
+    .env("PLUGIN_ARGS", serde_json::to_string(&plugin_args).unwrap())
+    .output()
+    .unwrap();
+  fs::remove_dir_all(&dir).unwrap();
+
+  let stdout = String::from_utf8(output.stdout).unwrap();
+  let findings = match stdout.trim() {
+    "" => Vec::new(),
+    report => serde_json::from_str::<Vec<Finding>>(report).unwrap(),
+  };
+  let mut functions = findings
+    .into_iter()
+    .map(|finding| finding.function)
+    .collect::<Vec<_>>();
+  functions.sort();
+  Output {
+    success: output.status.success(),
+    functions,
+    stderr: String::from_utf8(output.stderr).unwrap(),
+  }
+}
+
+#[test]
+fn test_function() {
+  let output = run_driver("all", "cli_test", &[]);
+  assert_eq!(output.functions, ["auth::login", "report"]);
+  assert!(!output.success, "explicit flows are errors by default");
+  assert!(output.stderr.contains(
+    r#"IFC config entry {"return":"cli_test::read_secret"} matches nothing in this crate"#
+  ));
+  assert!(
+    !output
+      .stderr
+      .contains(r#"{"return":"read_secret"} matches nothing"#)
+  );
+
+  let output = run_driver("glob", "cli_test", &["--function", "auth::*"]);
+  assert_eq!(output.functions, ["auth::login"]);
+  assert!(!output.stderr.contains("matches nothing"));
+
+  let output = run_driver("exact", "cli_test", &["--function", "report"]);
+  assert_eq!(output.functions, ["report"]);
+
+  let output = run_driver("none", "cli_test", &["--function", "auth"]);
+  assert!(output.functions.is_empty());
+}
+
+#[test]
+fn test_fail_on_findings() {
+  // `auth::login` only has an implicit flow, which is a warning.
+  let output = run_driver("warn", "cli_test", &["--function", "auth::*"]);
+  assert!(output.success);
+
+  let output = run_driver("fail", "cli_test", &[
+    "--function",
+    "auth::*",
+    "--fail-on-findings",
+  ]);
+  assert_eq!(output.functions, ["auth::login"]);
+  assert!(!output.success);
+
+  let output = run_driver("clean", "cli_test", &[
+    "--function",
+    "auth",
+    "--fail-on-findings",
+  ]);
+  assert!(output.success);
+}
+
+#[test]
+fn test_package() {
+  let output = run_driver("other", "other", &["--package", "cli_test"]);
+  assert!(output.functions.is_empty());
+  assert!(output.success);
+
+  let output = run_driver("selected", "cli_test", &["--package", "cli_test"]);
+  assert_eq!(output.functions, ["auth::login", "report"]);
+}
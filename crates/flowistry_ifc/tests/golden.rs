@@ -0,0 +1,9 @@
+#![feature(rustc_private)]
+
+use flowistry_ifc::test_utils;
+use test_log::test;
+
+#[test]
+fn golden() {
+  test_utils::run_tests("golden", test_utils::test_ifc_output);
+}
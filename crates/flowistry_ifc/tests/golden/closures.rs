@@ -0,0 +1,26 @@
+use flowistry_ifc_traits::{Secure, insecure_print};
+
+struct Password(&'static str);
+impl Secure for Password {}
+
+fn captured() {
+  let password = Password("hunter2");
+  let len = || password.0.len();
+  let n = len();
+  insecure_print!("{}", n); // ~ERROR insecure flow
+}
+
+fn argument() {
+  let password = Password("hunter2");
+  let double = |n: usize| n * 2;
+  let n = double(password.0.len());
+  insecure_print!("{}", n); // ~ERROR insecure flow
+}
+
+fn public_closure() {
+  let password = Password("hunter2");
+  let answer = || 42;
+  let n = answer();
+  insecure_print!("{}", n);
+  drop(password);
+}
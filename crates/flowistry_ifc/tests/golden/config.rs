@@ -0,0 +1,65 @@
+/* config {"sources": [{"return": "read_secret"}, {"param": {"function": "login", "index": 1}}, {"field": "User::password"}], "sinks": [{"function": "send", "args": [1]}, {"function": "<Console as Logger>::log"}]} */
+use flowistry_ifc_traits::insecure_print;
+
+fn read_secret() -> String {
+  String::from("hunter2")
+}
+
+fn read_public() -> String {
+  String::from("hello")
+}
+
+fn returned() {
+  let secret = read_secret();
+  insecure_print!("{}", secret); // ~ERROR insecure flow
+  let public = read_public();
+  insecure_print!("{}", public);
+}
+
+fn login(user: &str, password: &str) {
+  insecure_print!("{}", password); // ~ERROR insecure flow
+  insecure_print!("{}", user);
+}
+
+struct User {
+  name: String,
+  password: String,
+}
+
+fn field(user: &User) {
+  let password = &user.password;
+  insecure_print!("{}", password); // ~ERROR insecure flow
+  let name = &user.name;
+  insecure_print!("{}", name);
+}
+
+fn send(_port: usize, _length: usize) {}
+
+fn sink_args() {
+  let secret = read_secret();
+  let n = secret.len();
+  send(n, 0);
+  send(0, n); // ~ERROR insecure flow
+}
+
+trait Logger {
+  fn log(&self, message: &str);
+}
+
+struct Console;
+impl Logger for Console {
+  fn log(&self, _message: &str) {}
+}
+
+struct Silent;
+impl Logger for Silent {
+  fn log(&self, _message: &str) {}
+}
+
+fn trait_method() {
+  let secret = read_secret();
+  Console.log(&secret); // ~ERROR insecure flow
+  Silent.log(&secret);
+}
+
+fn main() {}
@@ -0,0 +1,42 @@
+use flowistry_ifc_traits::{Declassified, Secure, insecure_print, sanitize};
+
+struct Password(&'static str);
+impl Secure for Password {}
+
+struct PasswordHash(u64);
+impl Declassified for PasswordHash {}
+
+fn hash(s: &str) -> PasswordHash {
+  PasswordHash(s.len() as u64 * 31)
+}
+
+fn checksum(s: &str) -> u64 {
+  s.len() as u64 * 31
+}
+
+fn sanitized() {
+  let password = Password("hunter2");
+  let s = sanitize(password.0);
+  insecure_print!("{}", s);
+}
+
+fn unsanitized() {
+  let password = Password("hunter2");
+  let s = password.0.trim();
+  insecure_print!("{}", s); // ~ERROR insecure flow
+}
+
+fn declassified_return() {
+  let password = Password("hunter2");
+  let hash = hash(password.0);
+  let n = hash.0;
+  insecure_print!("{}", n);
+}
+
+fn plain_return() {
+  let password = Password("hunter2");
+  let n = checksum(password.0);
+  insecure_print!("{}", n); // ~ERROR insecure flow
+}
+
+fn main() {}
@@ -0,0 +1,38 @@
+use flowistry_ifc_traits::{Secure, declassify, insecure_print};
+
+struct Password(&'static str);
+impl Secure for Password {}
+
+fn direct() {
+  let password = Password("hunter2");
+  let s = password.0;
+  insecure_print!("{}", s); // ~ERROR insecure flow from secret data to insecure data
+}
+
+fn computed() {
+  let password = Password("hunter2");
+  let n = password.0.len() * 2 + 1;
+  insecure_print!("{}", n); // ~ERROR insecure flow
+}
+
+fn leak(s: &str) {
+  insecure_print!("{}", s);
+}
+
+fn through_helper() {
+  let password = Password("hunter2");
+  leak(password.0); // ~ERROR insecure flow
+}
+
+fn declassified() {
+  let password = Password("hunter2");
+  let n = declassify(password.0.len());
+  insecure_print!("{}", n);
+}
+
+fn public() {
+  let password = Password("hunter2");
+  let greeting = "hello";
+  insecure_print!("{}", greeting);
+  drop(password);
+}
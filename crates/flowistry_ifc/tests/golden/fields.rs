@@ -0,0 +1,46 @@
+use flowistry_ifc_traits as ifc;
+use flowistry_ifc_traits::insecure_print;
+
+#[ifc::annotated]
+struct User {
+  name: String,
+  #[ifc::secret]
+  password_hash: String,
+}
+
+fn secret_field(user: User) {
+  let hash = user.password_hash;
+  insecure_print!("{}", hash); // ~ERROR insecure flow
+}
+
+fn public_field(user: User) {
+  let name = user.name;
+  insecure_print!("{}", name);
+}
+
+fn local_fields() {
+  let user = User {
+    name: String::from("alice"),
+    password_hash: String::from("abc"),
+  };
+  let name = &user.name;
+  insecure_print!("{}", name);
+  let hash = &user.password_hash;
+  insecure_print!("{}", hash); // ~ERROR insecure flow
+}
+
+#[ifc::secret]
+fn session_token() -> String {
+  String::from("token")
+}
+
+fn secret_return() {
+  let token = session_token();
+  insecure_print!("{}", token); // ~ERROR insecure flow
+}
+
+#[ifc::annotated]
+fn secret_param(#[ifc::secret] pin: u32, attempts: u32) {
+  insecure_print!("{}", attempts);
+  insecure_print!("{}", pin); // ~ERROR insecure flow
+}
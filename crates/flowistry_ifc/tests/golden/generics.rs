@@ -0,0 +1,33 @@
+use std::fmt::Display;
+
+use flowistry_ifc_traits::{Secure, insecure_print};
+
+struct Password(&'static str);
+impl Secure for Password {}
+
+fn show<T: Display>(value: T) {
+  insecure_print!("{}", value);
+}
+
+fn through_generic() {
+  let password = Password("hunter2");
+  show(password.0); // ~ERROR insecure flow
+}
+
+struct Wrapper<T>(T);
+
+fn secure_wrapped<T>(wrapper: Wrapper<T>) -> T {
+  wrapper.0
+}
+
+fn through_generic_type() {
+  let password = Password("hunter2");
+  let s = secure_wrapped(Wrapper(password.0));
+  insecure_print!("{}", s); // ~ERROR insecure flow
+}
+
+fn public_generic() {
+  let password = Password("hunter2");
+  show(1u32);
+  drop(password);
+}
@@ -0,0 +1,57 @@
+use flowistry_ifc_traits::{Secure, insecure_print};
+
+struct Password(&'static str);
+impl Secure for Password {}
+
+fn branch() {
+  let password = Password("hunter2");
+  if password.0 == "hello" {
+    insecure_print!("Hello world!"); // ~WARN implicit flow from secret data to insecure data
+  }
+}
+
+fn matching() {
+  let password = Password("hunter2");
+  match password.0.len() {
+    0 => {
+      insecure_print!("empty"); // ~WARN implicit flow
+    }
+    _ => {}
+  }
+}
+
+fn early_return() {
+  let password = Password("hunter2");
+  if password.0.is_empty() {
+    return;
+  }
+  insecure_print!("done"); // ~WARN depends on the termination of a branch on secret data
+}
+
+fn looping() {
+  let password = Password("hunter2");
+  let mut i = 0;
+  while i < password.0.len() {
+    insecure_print!("tick"); // ~WARN depends on the termination
+    i += 1;
+  }
+}
+
+fn unrelated_branch(flag: bool) {
+  let password = Password("hunter2");
+  if flag {
+    insecure_print!("flag");
+  }
+  drop(password);
+}
+
+fn maybe_print(flag: bool) {
+  if flag {
+    insecure_print!("hi");
+  }
+}
+
+fn helper_branch() {
+  let password = Password("hunter2");
+  maybe_print(password.0.is_empty()); // ~WARN implicit flow from secret data to insecure data
+}
@@ -0,0 +1,62 @@
+use std::{fmt::Display, rc::Rc};
+
+use flowistry_ifc_traits::{InsecureString, Secure, insecure_print};
+
+struct Password(&'static str);
+impl Secure for Password {}
+
+fn bounded<T: Secure + Display>(value: T) {
+  insecure_print!("{}", value); // ~ERROR insecure flow
+}
+
+fn where_clause<T>(value: T)
+where
+  T: Secure + Display,
+{
+  insecure_print!("{}", value); // ~ERROR insecure flow
+}
+
+fn unbounded<T: Display>(value: T) {
+  insecure_print!("{}", value);
+}
+
+fn boxed() {
+  let password = Box::new(Password("hunter2"));
+  let n = password.0.len();
+  insecure_print!("{}", n); // ~ERROR insecure flow
+}
+
+fn vec_param(passwords: Vec<Password>) {
+  let n = passwords[0].0.len();
+  insecure_print!("{}", n); // ~ERROR insecure flow
+}
+
+fn mut_ref(password: &mut Password) {
+  let s = password.0;
+  insecure_print!("{}", s); // ~ERROR insecure flow
+}
+
+fn rc(password: Rc<Password>) {
+  let s = password.0;
+  insecure_print!("{}", s); // ~ERROR insecure flow
+}
+
+struct Account {
+  name: String,
+  passwords: Vec<Password>,
+}
+
+fn nested(account: &Account) {
+  let n = account.passwords.len();
+  insecure_print!("{}", n); // ~ERROR insecure flow
+  let name = &account.name;
+  insecure_print!("{}", name);
+}
+
+fn send(_messages: &[InsecureString]) {}
+
+fn insecure_container() {
+  let password = Password("hunter2");
+  let message = InsecureString(password.0.to_string());
+  send(&[message]); // ~ERROR insecure flow
+}
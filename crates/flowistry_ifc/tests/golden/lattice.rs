@@ -0,0 +1,48 @@
+use flowistry_ifc_traits::{FlowsTo, Label, Labeled};
+
+// Public < Internal < Confidential, and Finance and Engineering are incomparable.
+struct Public;
+struct Internal;
+struct Confidential;
+struct Finance;
+struct Engineering;
+impl Label for Public {}
+impl Label for Internal {}
+impl Label for Confidential {}
+impl Label for Finance {}
+impl Label for Engineering {}
+impl FlowsTo<Internal> for Public {}
+impl FlowsTo<Confidential> for Internal {}
+
+struct Notice(u32);
+impl Labeled<Public> for Notice {}
+struct Memo(u32);
+impl Labeled<Internal> for Memo {}
+struct Salary(u32);
+impl Labeled<Confidential> for Salary {}
+struct Budget(u32);
+impl Labeled<Finance> for Budget {}
+struct Roadmap(u32);
+impl Labeled<Engineering> for Roadmap {}
+
+fn upward(notice: &Notice) {
+  let _memo = Memo(notice.0 + 1);
+}
+
+fn transitive(notice: &Notice) {
+  let _salary = Salary(notice.0 * 2);
+}
+
+fn downward(salary: &Salary) {
+  let _notice = Notice(salary.0 / 2); // ~ERROR insecure flow from `Confidential` data to `Public` data
+}
+
+fn incomparable(budget: &Budget) {
+  let _roadmap = Roadmap(budget.0); // ~ERROR insecure flow from `Finance` data to `Engineering` data
+}
+
+fn same(budget: &Budget) {
+  let _copy = Budget(budget.0);
+}
+
+fn main() {}
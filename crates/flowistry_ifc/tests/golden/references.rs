@@ -0,0 +1,30 @@
+use flowistry_ifc_traits::{Secure, insecure_print};
+
+struct Password(&'static str);
+impl Secure for Password {}
+
+fn shared_reference() {
+  let password = Password("hunter2");
+  let r = &password;
+  let s = r.0;
+  insecure_print!("{}", s); // ~ERROR insecure flow
+}
+
+fn write_through(out: &mut usize, password: &Password) {
+  *out = password.0.len();
+}
+
+fn mutable_reference() {
+  let password = Password("hunter2");
+  let mut n = 0;
+  write_through(&mut n, &password);
+  insecure_print!("{}", n); // ~ERROR insecure flow
+}
+
+fn public_reference() {
+  let password = Password("hunter2");
+  let greeting = String::from("hello");
+  let r = &greeting;
+  insecure_print!("{}", r);
+  drop(password);
+}
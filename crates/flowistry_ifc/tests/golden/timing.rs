@@ -0,0 +1,31 @@
+/* -Dtiming_flow */
+use flowistry_ifc_traits::Secure;
+
+struct Key(u8);
+impl Secure for Key {}
+
+const TABLE: [u8; 4] = [1, 2, 3, 4];
+
+fn secret_branch(key: &Key) -> bool {
+  if key.0 == 0 { // ~ERROR branch condition depends on secret data
+    return true;
+  }
+  false
+}
+
+fn secret_index(key: &Key) -> u8 {
+  let index = key.0 as usize % 4;
+  TABLE[index] // ~ERROR array index depends on secret data
+}
+
+fn public(key: &Key, round: usize) -> usize {
+  let mut count = 0;
+  if round == 0 {
+    count += 1;
+  }
+  count += TABLE[round % 4] as usize;
+  let _key = key;
+  count
+}
+
+fn main() {}
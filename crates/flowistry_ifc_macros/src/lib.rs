@@ -0,0 +1,139 @@
+//! Attribute macros for secrecy annotations, re-exported by `flowistry_ifc_traits`.
+//!
+//! The macros replace each `#[ifc::secret]` annotation with
+//! `#[cfg_attr(flowistry_ifc, flowistry_ifc::secret)]`. `cargo ifc` sets `--cfg flowistry_ifc`
+//! and registers `flowistry_ifc` as a tool, so the checker finds the annotations in the HIR,
+//! while normal builds drop them. To avoid `unexpected_cfgs` warnings for `#[ifc::secret]` on
+//! functions, add `unexpected_cfgs = { level = "warn", check-cfg = ["cfg(flowistry_ifc)"] }`
+//! to the `[lints.rust]` table of `Cargo.toml`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{ToTokens, quote};
+use syn::{
+  Attribute, Error, Fields, FnArg, Item, Meta, Result, Signature, TraitItemFn,
+  parse_quote,
+};
+
+/// Marks the return value of a function as secret, e.g.
+///
+/// ```ignore
+/// use flowistry_ifc_traits as ifc;
+///
+/// #[ifc::secret]
+/// fn load_key(#[ifc::secret] passphrase: &str) -> Vec<u8> { .. }
+/// ```
+///
+/// The parameters of the function may also be annotated with `#[ifc::secret]`.
+#[proc_macro_attribute]
+pub fn secret(args: TokenStream, item: TokenStream) -> TokenStream {
+  expand(args, item, true)
+}
+
+/// Enables `#[ifc::secret]` annotations on the fields of a struct or enum, or on the parameters
+/// of a function, e.g.
+///
+/// ```ignore
+/// use flowistry_ifc_traits as ifc;
+///
+/// #[ifc::annotated]
+/// struct User {
+///   name: String,
+///   #[ifc::secret]
+///   password_hash: String,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn annotated(args: TokenStream, item: TokenStream) -> TokenStream {
+  expand(args, item, false)
+}
+
+fn expand(args: TokenStream, item: TokenStream, secret_return: bool) -> TokenStream {
+  let result = if args.is_empty() {
+    annotate(item.into(), secret_return)
+  } else {
+    Err(Error::new_spanned(
+      TokenStream2::from(args),
+      "unexpected arguments",
+    ))
+  };
+  result.unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn annotate(tokens: TokenStream2, secret_return: bool) -> Result<TokenStream2> {
+  // Trait methods without a default body are not items.
+  let Ok(mut item) = syn::parse2::<Item>(tokens.clone()) else {
+    let mut method = syn::parse2::<TraitItemFn>(tokens)?;
+    annotate_fn(&mut method.attrs, &mut method.sig, secret_return);
+    return Ok(method.into_token_stream());
+  };
+
+  match &mut item {
+    Item::Fn(item_fn) => annotate_fn(&mut item_fn.attrs, &mut item_fn.sig, secret_return),
+    Item::Struct(item_struct) if !secret_return => {
+      item_struct.attrs.push(allow_attr());
+      annotate_fields(&mut item_struct.fields);
+    }
+    Item::Enum(item_enum) if !secret_return => {
+      item_enum.attrs.push(allow_attr());
+      for variant in &mut item_enum.variants {
+        annotate_fields(&mut variant.fields);
+      }
+    }
+    _ if secret_return => {
+      return Err(Error::new_spanned(
+        item,
+        "`#[ifc::secret]` only applies to functions, fields and parameters, use \
+         `#[ifc::annotated]` to annotate the fields of a type",
+      ));
+    }
+    _ => {
+      return Err(Error::new_spanned(
+        item,
+        "`#[ifc::annotated]` only applies to structs, enums and functions",
+      ));
+    }
+  }
+  Ok(quote!(#item))
+}
+
+fn annotate_fn(attrs: &mut Vec<Attribute>, sig: &mut Signature, secret_return: bool) {
+  attrs.push(allow_attr());
+  if secret_return {
+    attrs.push(secret_attr());
+  }
+  for input in &mut sig.inputs {
+    match input {
+      FnArg::Receiver(receiver) => replace_annotations(&mut receiver.attrs),
+      FnArg::Typed(pat_type) => replace_annotations(&mut pat_type.attrs),
+    }
+  }
+}
+
+fn annotate_fields(fields: &mut Fields) {
+  for field in fields.iter_mut() {
+    replace_annotations(&mut field.attrs);
+  }
+}
+
+/// Replaces `#[secret]`, `#[ifc::secret]`, etc. with the attribute that the checker reads.
+fn replace_annotations(attrs: &mut [Attribute]) {
+  for attr in attrs {
+    let is_secret = matches!(&attr.meta, Meta::Path(path)
+      if path.segments.last().is_some_and(|segment| segment.ident == "secret"));
+    if is_secret {
+      *attr = secret_attr();
+    }
+  }
+}
+
+fn secret_attr() -> Attribute {
+  parse_quote!(#[cfg_attr(flowistry_ifc, flowistry_ifc::secret)])
+}
+
+/// Normal builds do not know the `flowistry_ifc` cfg. The lint is reported at the parent of an
+/// attribute, so this covers the annotations of fields and parameters, but not the annotation
+/// of a function's return value.
+fn allow_attr() -> Attribute {
+  parse_quote!(#[allow(unexpected_cfgs)])
+}
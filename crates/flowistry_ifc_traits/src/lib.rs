@@ -1,10 +1,83 @@
 use std::fmt;
 
+/// Secrecy annotations on fields, parameters and return values, for data that is only partly
+/// secret. Use these through an `ifc` alias for this crate:
+///
+/// ```
+/// use flowistry_ifc_traits as ifc;
+///
+/// #[ifc::annotated]
+/// struct User {
+///   name: String,
+///   #[ifc::secret]
+///   password_hash: String,
+/// }
+///
+/// #[ifc::secret]
+/// fn session_token(#[ifc::secret] user: &User) -> String {
+///   format!("{}:{}", user.name, user.password_hash)
+/// }
+/// ```
+pub use flowistry_ifc_macros::{annotated, secret};
+
+/// A type whose values are secret. Values that hold or point to a secret value, like a
+/// `&mut T`, `Box<T>` or `Vec<T>` where `T: Secure`, are also secret, and likewise for
+/// [`Insecure`] and [`Labeled`].
 pub trait Secure {}
 pub trait Insecure {}
 
+/// A type whose values are never secret, even when computed from secret data.
+///
+/// A call to a function that returns a `Declassified` type does not carry the secrecy of
+/// its arguments to its return value, e.g. `fn hash(password: &Password) -> PasswordHash`
+/// if `PasswordHash` implements `Declassified`.
+pub trait Declassified {}
+
+/// Releases a secret value, so the IFC checker no longer treats the result as secret.
+pub fn declassify<T>(value: T) -> T {
+  value
+}
+
+/// Marks a value as safe to pass to an insecure sink, e.g. after escaping or redacting it.
+///
+/// The IFC checker treats this the same as [`declassify`].
+pub fn sanitize<T>(value: T) -> T {
+  value
+}
+
+/// A security level in a user-defined lattice, e.g.
+///
+/// ```
+/// # use flowistry_ifc_traits::*;
+/// struct Public;
+/// struct Confidential;
+/// impl Label for Public {}
+/// impl Label for Confidential {}
+/// impl FlowsTo<Confidential> for Public {}
+///
+/// struct Salary(u32);
+/// impl Labeled<Confidential> for Salary {}
+/// ```
+///
+/// Label types must not be generic.
+pub trait Label {}
+
+/// Data labeled `Self` may flow into places labeled `L`.
+///
+/// Every label flows to itself, and the IFC checker takes the transitive closure of these impls,
+/// so only the covering relation of the lattice needs to be written out.
+pub trait FlowsTo<L: Label>: Label {}
+
+/// A type whose values are labeled `L`.
+///
+/// Labeled values are both sources and sinks: data labeled `A` must not flow into a value
+/// labeled `B` unless `A: FlowsTo<B>`.
+pub trait Labeled<L: Label> {}
+
 impl<T: Secure> Secure for &T {}
 
+impl<L: Label, T: Labeled<L>> Labeled<L> for &T {}
+
 impl Insecure for fmt::Arguments<'_> {}
 
 pub struct InsecureString(pub String);
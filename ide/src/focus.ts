@@ -16,12 +16,21 @@ interface PlaceInfo {
   range: Range;
   ranges: Range[];
   slice: Range[];
+  definite_slice: Range[];
+  possible_slice: Range[];
   direct_influence: Range[];
 }
 
+interface Caveat {
+  kind: string;
+  range: Range;
+  description: string;
+}
+
 interface Focus {
   place_info: PlaceInfo[];
   containers: Range[];
+  caveats: Caveat[];
 }
 
 class FocusBodyState {